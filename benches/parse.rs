@@ -0,0 +1,113 @@
+//! Parsing throughput for a traffic mix like that seen by a busy FSD server
+//!
+//! Run with `cargo bench`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use fsd_interface::{ParseOptions, parse_message, parse_message_ref, parse_message_with};
+
+const FAST_POSITION: &str = "^BAW123:51.4705000:-0.4619000:83.00:-27.00:4286582824:0.0000:0.0000:0.0000:0.0000:0.0000:0.0000:0.00";
+const SLOW_POSITION: &str = "#SLBAW123:51.4705000:-0.4619000:83.00:-27.00:4286582824:0.0000:0.0000:0.0000:0.0000:0.0000:0.0000:0.00";
+const FLIGHT_PLAN: &str = "$FPBAW123:*A:I:B738/M-SDE2E3FGHIJ1RWXY/LB1:450:EGLL:1200:1200:FL350:LEMD:2:10:3:30:LEPA:/V/:DCT";
+
+/// A name for each line, the line, and the number of times it appears in the mix.
+/// Position updates make up most of the traffic.
+const TRAFFIC: [(&str, &str, usize); 16] = [
+    ("fast_position", FAST_POSITION, 40),
+    (
+        "pilot_position",
+        "@N:BAW123:1200:1:51.47050:-0.46190:83:0:4286582824:-27",
+        20,
+    ),
+    ("slow_position", SLOW_POSITION, 10),
+    (
+        "stopped_position",
+        "#STBAW123:51.4705000:-0.4619000:83.00:0.00:4286582824:0.00",
+        5,
+    ),
+    (
+        "atc_position",
+        "%EGPH_M_APP:33670:4:100:5:55.95000:-3.37250:0",
+        10,
+    ),
+    (
+        "frequency_text",
+        "#TMBAW123:@22800:Good morning, climbing FL240: direct BPK",
+        3,
+    ),
+    (
+        "private_text",
+        "#TMEGLL_TWR:BAW123:Contact Heathrow ground 121.9",
+        2,
+    ),
+    ("client_query", "$CQEHAM_GND:@94835:WH:KLM167", 2),
+    (
+        "client_query_response",
+        "$CREGLL_ATIS:BAW123:ATIS:T:Heathrow information B",
+        1,
+    ),
+    ("shared_state", "#PCEGLL_TWR:EGLL_APP:CCP:ST:BAW123:EGLL", 2),
+    ("flight_plan", FLIGHT_PLAN, 1),
+    (
+        "plane_info",
+        "#SBBAW123:EGLL_TWR:PI:GEN:EQUIPMENT=B738:AIRLINE=BAW:LIVERY=BAW",
+        1,
+    ),
+    ("ping", "$PISERVER:BAW123:8415", 1),
+    ("pong", "$POBAW123:SERVER:8415", 1),
+    ("handoff_offer", "$HOEGLL_TWR:EGLL_APP:BAW123", 1),
+    ("heartbeat", "#DLSERVER:*:0:0", 1),
+];
+
+fn traffic_mix() -> Vec<&'static str> {
+    TRAFFIC
+        .iter()
+        .flat_map(|(_, line, count)| std::iter::repeat_n(*line, *count))
+        .collect()
+}
+
+fn traffic(c: &mut Criterion) {
+    let mix = traffic_mix();
+    for line in &mix {
+        assert!(parse_message(line).is_ok(), "{line}");
+    }
+
+    let mut group = c.benchmark_group("traffic_mix");
+    group.throughput(Throughput::Elements(mix.len() as u64));
+    group.bench_function("parse_message", |b| {
+        b.iter(|| {
+            for line in &mix {
+                let _ = black_box(parse_message(black_box(line)));
+            }
+        })
+    });
+    group.bench_function("parse_message_with_strict", |b| {
+        let options = ParseOptions::strict();
+        b.iter(|| {
+            for line in &mix {
+                let _ = black_box(parse_message_with(black_box(line), &options));
+            }
+        })
+    });
+    group.bench_function("parse_message_ref", |b| {
+        b.iter(|| {
+            for line in &mix {
+                let _ = black_box(parse_message_ref(black_box(line)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn message_types(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message_type");
+    group.throughput(Throughput::Elements(1));
+    for (name, line, _) in TRAFFIC {
+        group.bench_with_input(BenchmarkId::from_parameter(name), line, |b, line| {
+            b.iter(|| parse_message(black_box(line)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, traffic, message_types);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+//! Feeds arbitrary input to the parsers. Parsing may fail, but must never panic, and unknown
+//! messages must display exactly as they were received.
+
+#![no_main]
+
+use fsd_interface::{Charset, Dialect, FsdFramer, FsdMessage, FsdMessageType, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut framer = FsdFramer::new();
+    framer.push(data);
+    for _ in framer.by_ref() {}
+
+    for charset in [Charset::Windows1252, Charset::Latin1] {
+        if let Ok(message) = fsd_interface::parse_message_bytes(data, charset) {
+            inspect(&message);
+            let _ = message.encode_bytes(charset);
+        }
+    }
+
+    if let Ok(line) = std::str::from_utf8(data) {
+        if let Ok(message) = fsd_interface::parse_message(line) {
+            inspect(&message);
+            let displayed = message.to_string();
+            if let FsdMessageType::Unknown { .. } = message {
+                assert_eq!(line, displayed);
+            }
+        }
+        for dialect in [Dialect::Vatsim, Dialect::Classic, Dialect::Ivao] {
+            for options in [ParseOptions::strict(), ParseOptions::lenient()] {
+                let options = ParseOptions { dialect, ..options };
+                if let Ok(parsed) = fsd_interface::parse_message_with(line, &options) {
+                    inspect(&parsed.message);
+                    let _ = dialect.encode(&parsed.message);
+                }
+            }
+        }
+        if let Ok(message) = fsd_interface::parse_message_ref(line) {
+            let _ = message.to_owned();
+        }
+    }
+});
+
+/// Reads everything the [`FsdMessage`] trait exposes, which must not panic either
+fn inspect(message: &FsdMessageType) {
+    let _ = message.sender();
+    let _ = message.recipient();
+    let _ = message.violations();
+    let _ = message.try_encode();
+}
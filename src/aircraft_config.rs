@@ -1,11 +1,11 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, io, str::FromStr};
 
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 
 use crate::errors::FsdMessageParseError;
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[serde(default)]
 pub struct AircraftConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -42,13 +42,31 @@ impl FromStr for AircraftConfig {
 }
 impl Display for AircraftConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let serialised = json!({
-            "config": self,
-        });
-        write!(f, "{serialised}")
+        #[derive(Serialize)]
+        struct Wrapped<'a> {
+            config: &'a AircraftConfig,
+        }
+        serde_json::to_writer(FormatterWriter(f), &Wrapped { config: self })
+            .map_err(|_| std::fmt::Error)
+    }
+}
+
+/// Lets `serde_json` write straight into a [`Formatter`][std::fmt::Formatter], which only ever receives valid UTF-8
+struct FormatterWriter<'a, 'b>(&'a mut std::fmt::Formatter<'b>);
+
+impl io::Write for FormatterWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s =
+            std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0.write_str(s).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
-#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AircraftLightsConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub strobe_on: Option<bool>,
@@ -64,7 +82,7 @@ pub struct AircraftLightsConfig {
     pub logo_on: Option<bool>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AircraftEnginesConfig {
     #[serde(rename = "1", skip_serializing_if = "Option::is_none")]
     pub engine_1: Option<AircraftEngine>,
@@ -76,7 +94,7 @@ pub struct AircraftEnginesConfig {
     pub engine_4: Option<AircraftEngine>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AircraftEngine {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub on: Option<bool>,
@@ -0,0 +1,185 @@
+//! Compares messages that carry floating point values to the precision they are sent with
+
+use crate::{
+    enums::FsdMessageType,
+    messages::{
+        AtcPositionUpdateMessage, AtcSecondaryVisCentreMessage, CloudDataMessage,
+        PilotPositionUpdateMessage, VelocityPositionFastMessage, VelocityPositionSlowMessage,
+        VelocityPositionStoppedMessage,
+    },
+    util::encode_pitch_bank_heading,
+};
+
+/// Equality to the precision each field is sent with, for messages that carry floating point values.
+///
+/// A position parsed from the wire rarely compares equal to the one it was created from, because coordinates are
+/// rounded to 5 decimal places in `@` updates and 7 in `#SL` updates. Two messages are approximately equal if each
+/// of their fields would be sent as the same value.
+///
+/// # Example
+/// ```
+/// use fsd_interface::{ApproxEq, FsdMessageType};
+///
+/// let original = fsd_interface::parse_message("@N:BAW123:1200:1:51.47750:-0.46139:100:0:0:0").unwrap();
+/// let FsdMessageType::PilotPositionUpdateMessage(mut moved) = original.clone() else {
+///     panic!("expected a pilot position update");
+/// };
+///
+/// moved.latitude += 0.000001;
+/// let moved = FsdMessageType::PilotPositionUpdateMessage(moved);
+/// assert_ne!(original, moved);
+/// assert!(original.approx_eq(&moved));
+/// ```
+pub trait ApproxEq {
+    /// Returns `true` if every field of `self` and `other` would be sent as the same value
+    fn approx_eq(&self, other: &Self) -> bool;
+}
+
+/// Whether `a` and `b` are written the same with `places` decimal places, as `Display` writes them
+fn eq_to_places(a: f64, b: f64, places: usize) -> bool {
+    format!("{a:.places$}") == format!("{b:.places$}")
+}
+
+fn opt_eq_to_places(a: Option<f64>, b: Option<f64>, places: usize) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => eq_to_places(a, b, places),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Pitch, bank and heading are packed into a single integer, losing some precision
+fn eq_attitude(a: (f64, f64, f64, bool), b: (f64, f64, f64, bool)) -> bool {
+    encode_pitch_bank_heading(a.0, a.1, a.2, a.3) == encode_pitch_bank_heading(b.0, b.1, b.2, b.3)
+}
+
+impl ApproxEq for AtcPositionUpdateMessage {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.callsign == other.callsign
+            && self.frequencies == other.frequencies
+            && self.atc_type == other.atc_type
+            && self.vis_range == other.vis_range
+            && self.rating == other.rating
+            && eq_to_places(self.latitude, other.latitude, 5)
+            && eq_to_places(self.longitude, other.longitude, 5)
+            && self.elevation == other.elevation
+    }
+}
+
+impl ApproxEq for AtcSecondaryVisCentreMessage {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.callsign == other.callsign
+            && self.index == other.index
+            && eq_to_places(self.latitude, other.latitude, 5)
+            && eq_to_places(self.longitude, other.longitude, 5)
+    }
+}
+
+impl ApproxEq for PilotPositionUpdateMessage {
+    fn approx_eq(&self, other: &Self) -> bool {
+        // Altitudes are sent as whole feet, with the pressure altitude as a difference from the true altitude
+        let altitude_difference = |m: &Self| (m.pressure_altitude - m.true_altitude) as i32;
+        self.callsign == other.callsign
+            && self.transponder_mode == other.transponder_mode
+            && self.transponder_code == other.transponder_code
+            && self.rating == other.rating
+            && eq_to_places(self.latitude, other.latitude, 5)
+            && eq_to_places(self.longitude, other.longitude, 5)
+            && self.true_altitude as i32 == other.true_altitude as i32
+            && altitude_difference(self) == altitude_difference(other)
+            && self.ground_speed == other.ground_speed
+            && eq_attitude(
+                (self.pitch, self.bank, self.heading, self.on_ground),
+                (other.pitch, other.bank, other.heading, other.on_ground),
+            )
+    }
+}
+
+impl ApproxEq for VelocityPositionStoppedMessage {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.from == other.from
+            && eq_to_places(self.latitude, other.latitude, 7)
+            && eq_to_places(self.longitude, other.longitude, 7)
+            && eq_to_places(self.true_altitude, other.true_altitude, 2)
+            && eq_to_places(self.altitude_agl, other.altitude_agl, 2)
+            && eq_attitude(
+                (self.pitch, self.bank, self.heading, self.on_ground),
+                (other.pitch, other.bank, other.heading, other.on_ground),
+            )
+            && opt_eq_to_places(self.nose_gear_angle, other.nose_gear_angle, 2)
+    }
+}
+
+/// Implements [`ApproxEq`] for the fast and slow velocity updates, which share their fields and precision
+macro_rules! impl_velocity_approx_eq {
+    ($message: ty) => {
+        impl ApproxEq for $message {
+            fn approx_eq(&self, other: &Self) -> bool {
+                self.from == other.from
+                    && eq_to_places(self.latitude, other.latitude, 7)
+                    && eq_to_places(self.longitude, other.longitude, 7)
+                    && eq_to_places(self.true_altitude, other.true_altitude, 2)
+                    && eq_to_places(self.altitude_agl, other.altitude_agl, 2)
+                    && eq_attitude(
+                        (self.pitch, self.bank, self.heading, self.on_ground),
+                        (other.pitch, other.bank, other.heading, other.on_ground),
+                    )
+                    && eq_to_places(self.x_velocity, other.x_velocity, 4)
+                    && eq_to_places(self.y_velocity, other.y_velocity, 4)
+                    && eq_to_places(self.z_velocity, other.z_velocity, 4)
+                    && eq_to_places(self.pitch_rad_per_sec, other.pitch_rad_per_sec, 4)
+                    && eq_to_places(self.heading_rad_per_sec, other.heading_rad_per_sec, 4)
+                    && eq_to_places(self.bank_rad_per_sec, other.bank_rad_per_sec, 4)
+                    && opt_eq_to_places(self.nose_gear_angle, other.nose_gear_angle, 2)
+            }
+        }
+    };
+}
+
+impl_velocity_approx_eq!(VelocityPositionSlowMessage);
+impl_velocity_approx_eq!(VelocityPositionFastMessage);
+
+impl ApproxEq for CloudDataMessage {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.from == other.from
+            && self.to == other.to
+            && self.layers == other.layers
+            && self.thunderstorm == other.thunderstorm
+            && eq_to_places(self.visibility, other.visibility, 2)
+    }
+}
+
+/// Messages without floating point values are compared exactly
+impl ApproxEq for FsdMessageType {
+    fn approx_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                FsdMessageType::AtcPositionUpdateMessage(a),
+                FsdMessageType::AtcPositionUpdateMessage(b),
+            ) => a.approx_eq(b),
+            (
+                FsdMessageType::AtcSecondaryVisCentreMessage(a),
+                FsdMessageType::AtcSecondaryVisCentreMessage(b),
+            ) => a.approx_eq(b),
+            (
+                FsdMessageType::PilotPositionUpdateMessage(a),
+                FsdMessageType::PilotPositionUpdateMessage(b),
+            ) => a.approx_eq(b),
+            (
+                FsdMessageType::VelocityPositionStoppedMessage(a),
+                FsdMessageType::VelocityPositionStoppedMessage(b),
+            ) => a.approx_eq(b),
+            (
+                FsdMessageType::VelocityPositionSlowMessage(a),
+                FsdMessageType::VelocityPositionSlowMessage(b),
+            ) => a.approx_eq(b),
+            (
+                FsdMessageType::VelocityPositionFastMessage(a),
+                FsdMessageType::VelocityPositionFastMessage(b),
+            ) => a.approx_eq(b),
+            (FsdMessageType::CloudDataMessage(a), FsdMessageType::CloudDataMessage(b)) => {
+                a.approx_eq(b)
+            }
+            (a, b) => a == b,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! A small FSD server for private networks
+//!
+//! Listens on the address given as the first argument, or on port 6809 of every interface by default.
+
+use fsd_interface::server::{self, ServerConfig};
+use fsd_interface::session::DEFAULT_PORT;
+use tokio::net::TcpListener;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("0.0.0.0:{DEFAULT_PORT}"));
+    let listener = TcpListener::bind(&address).await?;
+    eprintln!("fsd-server listening on {}", listener.local_addr()?);
+    server::serve(listener, ServerConfig::default()).await;
+    Ok(())
+}
@@ -0,0 +1,257 @@
+//! A synchronous FSD client over [`std::net::TcpStream`], for tools that don't want an async runtime
+//!
+//! [`FsdClient`] runs a [`ClientSession`] as messages are read. Every call to [`FsdClient::recv`] answers the
+//! server's handshake and pings before returning the message, and follows `$XX` requests to move to another
+//! server. Unlike the async client, it doesn't reconnect when the connection drops.
+//!
+//! # Example
+//! ```
+//! use std::io::{BufRead, BufReader, Write};
+//! use std::net::TcpListener;
+//! use fsd_interface::blocking::FsdClient;
+//! use fsd_interface::session::SessionConfig;
+//! use fsd_interface::{Callsign, Cid, FsdMessageType, PilotRating, SimulatorType};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // A stand-in server
+//! let listener = TcpListener::bind("127.0.0.1:0")?;
+//! let port = listener.local_addr()?.port();
+//! let server = std::thread::spawn(move || -> std::io::Result<()> {
+//!     let (mut stream, _) = listener.accept()?;
+//!     let mut lines = BufReader::new(stream.try_clone()?).lines();
+//!     stream.write_all(b"$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6\r\n")?;
+//!     assert!(lines.next().unwrap()?.starts_with("$IDBAW123:SERVER:"));
+//!     assert!(lines.next().unwrap()?.starts_with("#APBAW123:SERVER:1234567:"));
+//!     stream.write_all(b"$PISERVER:BAW123:8415\r\n#TMEGLL_TWR:BAW123:Hello\r\n")?;
+//!     assert_eq!("$POBAW123:SERVER:8415", lines.next().unwrap()?);
+//!     assert_eq!("#TMBAW123:EGLL_TWR:Hi", lines.next().unwrap()?);
+//!     assert_eq!("#DPBAW123:1234567", lines.next().unwrap()?);
+//!     Ok(())
+//! });
+//!
+//! let config = SessionConfig::pilot(
+//!     Callsign::new("BAW123")?,
+//!     Cid::new("1234567")?,
+//!     "password",
+//!     "Jane Doe",
+//!     PilotRating::Student,
+//!     SimulatorType::Unknown,
+//! );
+//! let mut client = FsdClient::connect("127.0.0.1", port, config)?;
+//! assert_eq!("VATSIM FSD V3.43", client.wait_for_registration()?);
+//! loop {
+//!     if let FsdMessageType::TextMessage(text) = client.recv()? {
+//!         assert_eq!("Hello", text.message);
+//!         // Text that would break the line is rejected rather than sent
+//!         assert!(client.send_text(text.from, "Hi\r\n#DPBAW123").is_err());
+//!         client.send_text(text.from, "Hi")?;
+//!         break;
+//!     }
+//! }
+//! client.disconnect()?;
+//! server.join().unwrap()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::Display,
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Callsign, FsdFramer, FsdMessage, FsdMessageType, Recipient,
+    errors::FsdConnectionError,
+    framing,
+    messages::TextMessage,
+    session::{ClientSession, SessionConfig, SessionEvent, SessionState},
+};
+
+/// How long a read waits for data by default before [`FsdClient::recv`] returns a timeout error
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A blocking connection to an FSD server
+#[derive(Debug)]
+pub struct FsdClient {
+    host: String,
+    port: u16,
+    stream: TcpStream,
+    read_timeout: Option<Duration>,
+    framer: FsdFramer,
+    session: ClientSession,
+    server_version: Option<String>,
+}
+
+impl FsdClient {
+    /// Connects to `host`, with a read timeout of [`DEFAULT_READ_TIMEOUT`]. The login starts as soon as the
+    /// server's handshake is received.
+    pub fn connect(
+        host: impl Into<String>,
+        port: u16,
+        config: SessionConfig,
+    ) -> io::Result<FsdClient> {
+        let host = host.into();
+        let stream = open(&host, port, Some(DEFAULT_READ_TIMEOUT))?;
+        Ok(FsdClient {
+            host,
+            port,
+            stream,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            framer: FsdFramer::new(),
+            session: ClientSession::new(config, Instant::now()),
+            server_version: None,
+        })
+    }
+
+    /// The server the client is connected to, which changes if the server moves it with `$XX`
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn callsign(&self) -> Callsign {
+        self.session.config().callsign
+    }
+
+    pub fn session(&self) -> &ClientSession {
+        &self.session
+    }
+
+    /// Sets how long [`recv`][FsdClient::recv] waits for data before returning an error. Waits forever if `None`.
+    ///
+    /// The session's own timeouts are only checked when a read times out, so they are never reached without one.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)?;
+        self.read_timeout = timeout;
+        Ok(())
+    }
+
+    /// Receives messages until the client has registered, and returns the server's version
+    pub fn wait_for_registration(&mut self) -> Result<String, FsdConnectionError> {
+        loop {
+            if let Some(version) = self.server_version.take() {
+                return Ok(version);
+            }
+            self.recv()?;
+        }
+    }
+
+    /// Receives the next message from the server, after answering it if the session needs to
+    ///
+    /// A line that can't be decoded returns [`FsdConnectionError::Framing`], and a read that times out returns
+    /// [`FsdConnectionError::Io`]. Both can be retried. Once the session has closed, for example after a fatal
+    /// `$ER` error, every call returns [`FsdConnectionError::Closed`].
+    pub fn recv(&mut self) -> Result<FsdMessageType, FsdConnectionError> {
+        loop {
+            if let SessionState::Closed(reason) = self.session.state() {
+                return Err(FsdConnectionError::Closed(reason.clone()));
+            }
+            if let Some(frame) = self.framer.next() {
+                let message = frame?;
+                self.session.handle_message(&message, Instant::now());
+                self.flush_session()?;
+                return Ok(message);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Sends a message to the server, after checking that it serialises to a valid line.
+    ///
+    /// A message that doesn't, for example a text message containing a line break, isn't sent and
+    /// returns an error of kind [`ErrorKind::InvalidInput`] wrapping the [`FsdEncodeError`][crate::errors::FsdEncodeError].
+    pub fn send_message(&mut self, message: &impl FsdMessage) -> io::Result<()> {
+        let line = message
+            .try_encode()
+            .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+        self.send(&line)
+    }
+
+    /// Sends a line to the server as it is, without checking it. Any value that displays as an FSD message can
+    /// be sent, including raw text, so a value containing a line break is sent as more than one line. Prefer
+    /// [`send_message`][FsdClient::send_message] for messages.
+    pub fn send(&mut self, message: &impl Display) -> io::Result<()> {
+        let mut line = Vec::new();
+        framing::encode_line(message, &mut line);
+        self.stream.write_all(&line)
+    }
+
+    /// Sends a text message to a callsign, a frequency, or one of the broadcast recipients. Fails like
+    /// [`send_message`][FsdClient::send_message] if the text can't be sent.
+    pub fn send_text(
+        &mut self,
+        to: impl Into<Recipient>,
+        message: impl Into<String>,
+    ) -> io::Result<()> {
+        let text = TextMessage::new(self.callsign(), to, message);
+        self.send_message(&text)
+    }
+
+    /// Deregisters from the server if the client has registered, and closes the connection
+    pub fn disconnect(mut self) -> io::Result<()> {
+        self.session.disconnect();
+        self.flush_session()?;
+        self.stream.shutdown(Shutdown::Both)
+    }
+
+    /// Reads more data into the framer, closing the session if one of its deadlines passes while waiting
+    fn fill(&mut self) -> Result<(), FsdConnectionError> {
+        let mut buffer = [0; 4096];
+        match self.stream.read(&mut buffer) {
+            Ok(0) => Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
+            Ok(read) => {
+                self.framer.push(&buffer[..read]);
+                Ok(())
+            }
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                self.session.handle_timeout(Instant::now());
+                self.flush_session()?;
+                match self.session.state() {
+                    SessionState::Closed(reason) => Err(FsdConnectionError::Closed(reason.clone())),
+                    _ => Err(error.into()),
+                }
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Sends what the session has queued, and acts on its events
+    fn flush_session(&mut self) -> io::Result<()> {
+        while let Some(message) = self.session.poll_transmit() {
+            let line = message
+                .try_encode()
+                .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+            self.stream.write_all(line.as_bytes())?;
+            self.stream.write_all(framing::LINE_TERMINATOR.as_bytes())?;
+        }
+        while let Some(event) = self.session.poll_event() {
+            match event {
+                SessionEvent::Registered { server_version } => {
+                    self.server_version = Some(server_version)
+                }
+                SessionEvent::ChangeServer { hostname } => self.change_server(hostname)?,
+                SessionEvent::Error(_) | SessionEvent::Closed(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves to another server on the same port, starting a new session there
+    fn change_server(&mut self, hostname: String) -> io::Result<()> {
+        let stream = open(&hostname, self.port, self.read_timeout)?;
+        let _ = self.stream.shutdown(Shutdown::Both);
+        self.stream = stream;
+        self.host = hostname;
+        self.framer = FsdFramer::new();
+        self.session = ClientSession::new(self.session.config().clone(), Instant::now());
+        Ok(())
+    }
+}
+
+fn open(host: &str, port: u16, read_timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect((host, port))?;
+    stream.set_read_timeout(read_timeout)?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
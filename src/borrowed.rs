@@ -0,0 +1,555 @@
+//! Contains borrowed, zero-copy versions of the high-frequency message types
+//!
+//! Position updates make up the bulk of FSD traffic, and most consumers discard the majority of
+//! them after a quick look at the callsign or position. [`parse_message_ref`][crate::parse_message_ref]
+//! parses these messages straight from the input line into an [`FsdMessageRef`] without allocating.
+//! Every other message type is identified by its prefix only, and is fully parsed on demand by
+//! [`FsdMessageRef::to_owned`].
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{parse_message_ref, FsdMessageRef, FsdMessageType};
+//!
+//! let line = "@N:BAW123:1200:1:51.47050:-0.46190:83:0:4286582824:-27";
+//! let message = parse_message_ref(line).unwrap();
+//! if let FsdMessageRef::PilotPositionUpdateMessage(position) = message {
+//!     assert_eq!("BAW123", position.callsign);
+//!     assert_eq!(0, position.ground_speed);
+//! }
+//! assert!(matches!(
+//!     message.to_owned().unwrap(),
+//!     FsdMessageType::PilotPositionUpdateMessage(_)
+//! ));
+//! ```
+
+use crate::{
+    Callsign, Dialect, FsdMessageType, PilotRating, RadioFrequency, TransponderCode,
+    TransponderMode,
+    enums::{AtcRating, AtcType},
+    errors::FsdMessageParseError,
+    messages::{
+        AtcPositionUpdateMessage, PilotPositionUpdateMessage, VelocityPositionFastMessage,
+        VelocityPositionSlowMessage, VelocityPositionStoppedMessage,
+    },
+    options::{ParseContext, ParseFields, ParseWarning},
+    prefix::Prefix,
+    util::{
+        self, check_num_fields, impl_try_from_fields, parse_field, parse_field_or,
+        parse_identifier, parse_sender, strip_prefix,
+    },
+};
+
+/// Maximum number of fields any of the borrowed message types reads
+const MAX_FIELDS: usize = 13;
+
+/// A borrowed FSD message, parsed without allocating.
+///
+/// Only the position update messages are parsed eagerly. Any other valid-looking line is returned
+/// as [`FsdMessageRef::Other`] and can be fully parsed with [`FsdMessageRef::to_owned`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FsdMessageRef<'a> {
+    AtcPositionUpdateMessage(AtcPositionUpdateRef<'a>),
+    PilotPositionUpdateMessage(PilotPositionUpdateRef),
+    VelocityPositionStoppedMessage(VelocityPositionStoppedRef),
+    VelocityPositionSlowMessage(VelocityPositionRef),
+    VelocityPositionFastMessage(VelocityPositionRef),
+    /// Any other message, left unparsed
+    Other(&'a str),
+}
+
+impl<'a> FsdMessageRef<'a> {
+    pub(crate) fn identify(message: &'a str) -> Result<FsdMessageRef<'a>, FsdMessageParseError> {
+        let (fields, num_fields) = split_fields(message);
+        let fields = &fields[..num_fields.min(MAX_FIELDS)];
+        Self::identify_fields(message, fields, &mut ParseContext::default()).map_err(|e| {
+            // Only the fields that were read are passed down, so make sure the context has the whole line
+            let mut e = e.in_line(fields);
+            if let FsdMessageParseError::WithContext { context, .. } = &mut e {
+                context.raw_line = message.to_string();
+            }
+            e
+        })
+    }
+
+    fn identify_fields(
+        message: &'a str,
+        fields: &[&'a str],
+        cx: &mut ParseContext,
+    ) -> Result<FsdMessageRef<'a>, FsdMessageParseError> {
+        let message = match Prefix::of(message) {
+            Some(Prefix::PilotPosition) => Self::PilotPositionUpdateMessage(cx.parse(fields)?),
+            Some(Prefix::VelocityFast) => {
+                Self::VelocityPositionFastMessage(VelocityPositionRef::parse_fields(fields, 1, cx)?)
+            }
+            Some(Prefix::VelocitySlow) => {
+                Self::VelocityPositionSlowMessage(VelocityPositionRef::parse_fields(fields, 3, cx)?)
+            }
+            Some(Prefix::VelocityStopped) => {
+                Self::VelocityPositionStoppedMessage(cx.parse(fields)?)
+            }
+            Some(Prefix::AtcPosition) => Self::AtcPositionUpdateMessage(cx.parse(fields)?),
+            _ => Self::Other(message),
+        };
+        Ok(message)
+    }
+
+    /// Converts the borrowed message into an owned [`FsdMessageType`].
+    ///
+    /// This is infallible for the eagerly parsed position messages. [`FsdMessageRef::Other`] is
+    /// parsed in full at this point, so may still return an error.
+    pub fn to_owned(&self) -> Result<FsdMessageType, FsdMessageParseError> {
+        Ok(match self {
+            FsdMessageRef::AtcPositionUpdateMessage(m) => {
+                FsdMessageType::AtcPositionUpdateMessage(m.to_owned())
+            }
+            FsdMessageRef::PilotPositionUpdateMessage(m) => {
+                FsdMessageType::PilotPositionUpdateMessage(m.to_owned())
+            }
+            FsdMessageRef::VelocityPositionStoppedMessage(m) => {
+                FsdMessageType::VelocityPositionStoppedMessage(m.to_owned())
+            }
+            FsdMessageRef::VelocityPositionSlowMessage(m) => {
+                FsdMessageType::VelocityPositionSlowMessage(m.to_owned())
+            }
+            FsdMessageRef::VelocityPositionFastMessage(m) => {
+                FsdMessageType::VelocityPositionFastMessage(m.to_owned().into())
+            }
+            FsdMessageRef::Other(line) => crate::parse_message(line)?,
+        })
+    }
+}
+
+impl_try_from_fields!(PilotPositionUpdateRef, VelocityPositionStoppedRef);
+
+/// Splits a line on `:` into a fixed-size array, returning the array and the total number of fields in the line
+fn split_fields(line: &str) -> ([&str; MAX_FIELDS], usize) {
+    let mut fields = [""; MAX_FIELDS];
+    let mut count = 0;
+    for field in line.split(':') {
+        if let Some(slot) = fields.get_mut(count) {
+            *slot = field;
+        }
+        count += 1;
+    }
+    (fields, count)
+}
+
+/// Borrowed version of [`AtcPositionUpdateMessage`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtcPositionUpdateRef<'a> {
+    pub callsign: Callsign,
+    /// Frequencies in their short wire form, e.g. `24500&27000`
+    pub frequencies: &'a str,
+    pub atc_type: AtcType,
+    pub vis_range: u32,
+    pub rating: AtcRating,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: i32,
+}
+
+impl<'a> TryFrom<&[&'a str]> for AtcPositionUpdateRef<'a> {
+    type Error = FsdMessageParseError;
+    fn try_from(fields: &[&'a str]) -> Result<Self, Self::Error> {
+        ParseContext::default().parse(fields)
+    }
+}
+
+impl<'a> ParseFields<'a> for AtcPositionUpdateRef<'a> {
+    fn parse_fields(
+        fields: &[&'a str],
+        cx: &mut ParseContext,
+    ) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 7..=8);
+        for frequency in fields[1].split(['&', '@']).filter(|x| !x.is_empty()) {
+            if let Err(e) = frequency.parse::<RadioFrequency>() {
+                cx.deviation(
+                    ParseWarning::SkippedValue {
+                        field: "frequencies",
+                        value: frequency.to_string(),
+                    },
+                    e.at_field(fields, 1, "frequencies"),
+                )?;
+            }
+        }
+        let elevation = match fields.get(7).map(|x| x.parse()) {
+            None => 0,
+            Some(Ok(elevation)) => elevation,
+            Some(Err(_)) => {
+                cx.deviation(
+                    ParseWarning::DefaultedField {
+                        field: "elevation",
+                        value: fields[7].to_string(),
+                    },
+                    FsdMessageParseError::InvalidAltitude(fields[7].to_string()).at_field(
+                        fields,
+                        7,
+                        "elevation",
+                    ),
+                )?;
+                0
+            }
+        };
+        Ok(AtcPositionUpdateRef {
+            callsign: parse_sender(cx, fields, 1, "callsign")?,
+            frequencies: fields[1],
+            atc_type: parse_field(fields, 2, "atc_type")?,
+            vis_range: parse_field_or(
+                fields,
+                3,
+                "vis_range",
+                FsdMessageParseError::InvalidVisRange,
+            )?,
+            rating: parse_field(fields, 4, "rating")?,
+            latitude: parse_field_or(
+                fields,
+                5,
+                "latitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            longitude: parse_field_or(
+                fields,
+                6,
+                "longitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            elevation,
+        })
+    }
+}
+
+impl AtcPositionUpdateRef<'_> {
+    /// Iterates over the valid frequencies in the message
+    pub fn frequencies(&self) -> impl Iterator<Item = RadioFrequency> + '_ {
+        self.frequencies
+            .split(['&', '@'])
+            .filter_map(|x| x.parse().ok())
+    }
+
+    pub fn to_owned(&self) -> AtcPositionUpdateMessage {
+        AtcPositionUpdateMessage::new(
+            self.callsign,
+            util::split_frequencies(self.frequencies),
+            self.atc_type,
+            self.vis_range,
+            self.rating,
+            self.latitude,
+            self.longitude,
+            self.elevation,
+        )
+    }
+}
+
+/// Borrowed version of [`PilotPositionUpdateMessage`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PilotPositionUpdateRef {
+    pub callsign: Callsign,
+    pub transponder_mode: TransponderMode,
+    pub transponder_code: TransponderCode,
+    pub rating: PilotRating,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub true_altitude: f64,
+    pub pressure_altitude: f64,
+    pub ground_speed: u32,
+    pub pitch: f64,
+    pub bank: f64,
+    pub heading: f64,
+    pub on_ground: bool,
+}
+
+impl ParseFields<'_> for PilotPositionUpdateRef {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        // IVAO clients don't send the altitude difference
+        let ivao = cx.dialect() == Dialect::Ivao;
+        if ivao {
+            check_num_fields!(cx, fields, 9..=10);
+        } else {
+            check_num_fields!(cx, fields, 10..=10);
+        }
+        let first = strip_prefix(fields, 1)?;
+
+        let true_altitude = parse_field_or(
+            fields,
+            6,
+            "true_altitude",
+            FsdMessageParseError::InvalidAltitude,
+        )?;
+        let alt_diff: f64 = if ivao && fields.len() == 9 {
+            0.0
+        } else {
+            parse_field_or(
+                fields,
+                9,
+                "altitude_difference",
+                FsdMessageParseError::InvalidAltitudeDifference,
+            )?
+        };
+        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(parse_field_or(
+            fields,
+            8,
+            "pitch_bank_heading",
+            FsdMessageParseError::InvalidPitchBankHeading,
+        )?);
+
+        Ok(PilotPositionUpdateRef {
+            callsign: parse_identifier(cx, fields, 1, "callsign")?,
+            transponder_mode: first
+                .parse()
+                .map_err(|e: FsdMessageParseError| e.at_field(fields, 0, "transponder_mode"))?,
+            transponder_code: parse_field(fields, 2, "transponder_code")?,
+            rating: parse_field(fields, 3, "rating")?,
+            latitude: parse_field_or(
+                fields,
+                4,
+                "latitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            longitude: parse_field_or(
+                fields,
+                5,
+                "longitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            true_altitude,
+            pressure_altitude: true_altitude + alt_diff,
+            ground_speed: parse_field_or(
+                fields,
+                7,
+                "ground_speed",
+                FsdMessageParseError::InvalidSpeed,
+            )?,
+            pitch,
+            bank,
+            heading,
+            on_ground,
+        })
+    }
+}
+
+impl PilotPositionUpdateRef {
+    pub fn to_owned(&self) -> PilotPositionUpdateMessage {
+        PilotPositionUpdateMessage::new(
+            self.callsign,
+            self.transponder_mode,
+            self.transponder_code,
+            self.rating,
+            self.latitude,
+            self.longitude,
+            self.true_altitude,
+            self.pressure_altitude,
+            self.ground_speed,
+            self.pitch,
+            self.bank,
+            self.heading,
+            self.on_ground,
+        )
+    }
+}
+
+/// Borrowed version of [`VelocityPositionStoppedMessage`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityPositionStoppedRef {
+    pub from: Callsign,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub true_altitude: f64,
+    pub altitude_agl: f64,
+    pub pitch: f64,
+    pub bank: f64,
+    pub heading: f64,
+    pub on_ground: bool,
+    pub nose_gear_angle: Option<f64>,
+}
+
+impl ParseFields<'_> for VelocityPositionStoppedRef {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 6..=7);
+        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(parse_field_or(
+            fields,
+            5,
+            "pitch_bank_heading",
+            FsdMessageParseError::InvalidPitchBankHeading,
+        )?);
+        let nose_gear_angle = if fields.len() > 6 {
+            Some(parse_field_or(
+                fields,
+                6,
+                "nose_gear_angle",
+                FsdMessageParseError::InvalidNosewheelAngle,
+            )?)
+        } else {
+            None
+        };
+        Ok(VelocityPositionStoppedRef {
+            from: parse_sender(cx, fields, 3, "from")?,
+            latitude: parse_field_or(
+                fields,
+                1,
+                "latitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            longitude: parse_field_or(
+                fields,
+                2,
+                "longitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            true_altitude: parse_field_or(
+                fields,
+                3,
+                "true_altitude",
+                FsdMessageParseError::InvalidAltitude,
+            )?,
+            altitude_agl: parse_field_or(
+                fields,
+                4,
+                "altitude_agl",
+                FsdMessageParseError::InvalidAltitude,
+            )?,
+            pitch,
+            bank,
+            heading,
+            on_ground,
+            nose_gear_angle,
+        })
+    }
+}
+
+impl VelocityPositionStoppedRef {
+    pub fn to_owned(&self) -> VelocityPositionStoppedMessage {
+        VelocityPositionStoppedMessage::new(
+            self.from,
+            self.latitude,
+            self.longitude,
+            self.true_altitude,
+            self.altitude_agl,
+            self.pitch,
+            self.bank,
+            self.heading,
+            self.on_ground,
+            self.nose_gear_angle,
+        )
+    }
+}
+
+/// Borrowed version of [`VelocityPositionSlowMessage`] and [`VelocityPositionFastMessage`], which share a layout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityPositionRef {
+    pub from: Callsign,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub true_altitude: f64,
+    pub altitude_agl: f64,
+    pub pitch: f64,
+    pub bank: f64,
+    pub heading: f64,
+    pub on_ground: bool,
+    pub x_velocity: f64,
+    pub y_velocity: f64,
+    pub z_velocity: f64,
+    pub pitch_rad_per_sec: f64,
+    pub heading_rad_per_sec: f64,
+    pub bank_rad_per_sec: f64,
+    pub nose_gear_angle: Option<f64>,
+}
+
+impl VelocityPositionRef {
+    /// Parses the fields of a `#SL` or `^` message. `prefix_len` is the length of the message prefix on the first field.
+    pub(crate) fn parse_fields(
+        fields: &[&str],
+        prefix_len: usize,
+        cx: &mut ParseContext,
+    ) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 12..=13);
+        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(parse_field_or(
+            fields,
+            5,
+            "pitch_bank_heading",
+            FsdMessageParseError::InvalidPitchBankHeading,
+        )?);
+        let nose_gear_angle = if fields.len() > 12 {
+            Some(parse_field_or(
+                fields,
+                12,
+                "nose_gear_angle",
+                FsdMessageParseError::InvalidNosewheelAngle,
+            )?)
+        } else {
+            None
+        };
+        let velocity = |i: usize, name: &'static str| {
+            parse_field_or(
+                fields,
+                i,
+                name,
+                FsdMessageParseError::InvalidPositionVelocity,
+            )
+        };
+        Ok(VelocityPositionRef {
+            from: parse_sender(cx, fields, prefix_len, "from")?,
+            latitude: parse_field_or(
+                fields,
+                1,
+                "latitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            longitude: parse_field_or(
+                fields,
+                2,
+                "longitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            true_altitude: parse_field_or(
+                fields,
+                3,
+                "true_altitude",
+                FsdMessageParseError::InvalidAltitude,
+            )?,
+            altitude_agl: parse_field_or(
+                fields,
+                4,
+                "altitude_agl",
+                FsdMessageParseError::InvalidAltitude,
+            )?,
+            pitch,
+            bank,
+            heading,
+            on_ground,
+            x_velocity: velocity(6, "x_velocity")?,
+            y_velocity: velocity(7, "y_velocity")?,
+            z_velocity: velocity(8, "z_velocity")?,
+            pitch_rad_per_sec: velocity(9, "pitch_rad_per_sec")?,
+            heading_rad_per_sec: velocity(10, "heading_rad_per_sec")?,
+            bank_rad_per_sec: velocity(11, "bank_rad_per_sec")?,
+            nose_gear_angle,
+        })
+    }
+
+    pub fn to_owned(&self) -> VelocityPositionSlowMessage {
+        VelocityPositionSlowMessage::new(
+            self.from,
+            self.latitude,
+            self.longitude,
+            self.true_altitude,
+            self.altitude_agl,
+            self.pitch,
+            self.bank,
+            self.heading,
+            self.on_ground,
+            self.x_velocity,
+            self.y_velocity,
+            self.z_velocity,
+            self.pitch_rad_per_sec,
+            self.heading_rad_per_sec,
+            self.bank_rad_per_sec,
+            self.nose_gear_angle,
+        )
+    }
+}
+
+impl From<VelocityPositionRef> for VelocityPositionFastMessage {
+    fn from(value: VelocityPositionRef) -> Self {
+        value.to_owned().into()
+    }
+}
@@ -0,0 +1,107 @@
+//! Character sets used by FSD clients for free text
+//!
+//! Modern clients send UTF-8, but many older ones send text messages, real names and flight plan remarks in
+//! Windows-1252 or Latin-1. Everything else in an FSD message is ASCII, which all three encode identically, so a
+//! whole line can be decoded or encoded in one go.
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{Charset, FsdMessage, FsdMessageType};
+//!
+//! // "Café" and a right single quote, as sent by a Windows-1252 client
+//! let line = b"#TMLFPG_TWR:AFR123:Caf\xe9 \x92s";
+//! let message = fsd_interface::parse_message_bytes(line, Charset::Windows1252).unwrap();
+//! let FsdMessageType::TextMessage(text) = &message else {
+//!     panic!("expected a text message");
+//! };
+//! assert_eq!("Café \u{2019}s", text.message);
+//! assert_eq!(line.to_vec(), message.encode_bytes(Charset::Windows1252));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::errors::FsdMessageParseError;
+
+/// The characters Windows-1252 maps bytes `0x80` to `0x9F` to. The five bytes Windows-1252 leaves undefined are
+/// mapped to the matching C1 control characters, as web browsers do, so that every byte can be decoded and
+/// encoded back unchanged.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Written in place of a character the target charset can't represent
+const REPLACEMENT: u8 = b'?';
+
+/// The character set of an FSD line on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Charset {
+    /// UTF-8, used by modern clients and servers
+    #[default]
+    Utf8,
+    /// Windows-1252, used by older Windows clients. A superset of Latin-1 for printable characters.
+    Windows1252,
+    /// ISO-8859-1
+    Latin1,
+}
+
+impl Charset {
+    /// Decodes a line received in this charset. Windows-1252 and Latin-1 decode any bytes, but a line that
+    /// isn't valid UTF-8 is rejected.
+    pub fn decode(self, bytes: &[u8]) -> Result<Cow<'_, str>, FsdMessageParseError> {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            // ASCII is the same in every charset, and is all most lines contain
+            if self == Charset::Utf8 || text.is_ascii() {
+                return Ok(Cow::Borrowed(text));
+            }
+        }
+        match self {
+            Charset::Utf8 => Err(FsdMessageParseError::InvalidUtf8),
+            Charset::Windows1252 => Ok(Cow::Owned(
+                bytes.iter().map(|&b| windows_1252_char(b)).collect(),
+            )),
+            Charset::Latin1 => Ok(Cow::Owned(bytes.iter().map(|&b| char::from(b)).collect())),
+        }
+    }
+
+    /// Encodes text for sending in this charset. Characters the charset can't represent are replaced with `?`.
+    pub fn encode(self, text: &str) -> Cow<'_, [u8]> {
+        if self == Charset::Utf8 || text.is_ascii() {
+            return Cow::Borrowed(text.as_bytes());
+        }
+        let mut bytes = Vec::with_capacity(text.len());
+        self.encode_into(text, &mut bytes);
+        Cow::Owned(bytes)
+    }
+
+    /// Appends `text`, encoded in this charset, to `dst`. Characters the charset can't represent are replaced with `?`.
+    pub fn encode_into(self, text: &str, dst: &mut Vec<u8>) {
+        match self {
+            Charset::Utf8 => dst.extend_from_slice(text.as_bytes()),
+            Charset::Windows1252 => dst.extend(text.chars().map(windows_1252_byte)),
+            Charset::Latin1 => {
+                dst.extend(text.chars().map(|c| u8::try_from(c).unwrap_or(REPLACEMENT)))
+            }
+        }
+    }
+}
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+fn windows_1252_byte(c: char) -> u8 {
+    match u8::try_from(c) {
+        Ok(byte) if !(0x80..=0x9F).contains(&byte) => byte,
+        _ => WINDOWS_1252_HIGH
+            .iter()
+            .position(|&high| high == c)
+            .map_or(REPLACEMENT, |index| 0x80 + index as u8),
+    }
+}
@@ -0,0 +1,507 @@
+//! An async FSD client built on tokio, enabled with the `tokio` feature
+//!
+//! [`FsdClient`] connects to a server and logs in with a [`ClientSession`], then keeps the connection going in a
+//! background task. It answers pings, moves to another server when the server sends `$XX`, and reconnects with
+//! exponential backoff when the connection drops. Incoming messages, and changes to the connection, are read
+//! with [`FsdClient::recv`] or by using the client as a `Stream`.
+//!
+//! # Example
+//! ```
+//! use fsd_interface::client::{ClientEvent, FsdClient};
+//! use fsd_interface::session::{CloseReason, SessionConfig};
+//! use fsd_interface::{Callsign, Cid, FsdMessageType, PilotRating, SimulatorType};
+//! use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+//! use tokio::net::TcpListener;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! // A stand-in server, which moves the client to another server (itself) once it has registered
+//! let listener = TcpListener::bind("127.0.0.1:0").await?;
+//! let port = listener.local_addr()?.port();
+//! let server = tokio::spawn(async move {
+//!     for connection in 0..2 {
+//!         let (stream, _) = listener.accept().await?;
+//!         let (reader, mut writer) = stream.into_split();
+//!         let mut lines = BufReader::new(reader).lines();
+//!         writer.write_all(b"$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6\r\n").await?;
+//!         assert!(lines.next_line().await?.unwrap().starts_with("$IDBAW123:SERVER:"));
+//!         assert!(lines.next_line().await?.unwrap().starts_with("#APBAW123:SERVER:1234567:"));
+//!         if connection == 0 {
+//!             writer.write_all(b"$XXSERVER:BAW123:127.0.0.1\r\n").await?;
+//!             continue;
+//!         }
+//!         assert_eq!("#TMBAW123:EGLL_TWR:Hello", lines.next_line().await?.unwrap());
+//!         writer.write_all(b"$PISERVER:BAW123:8415\r\n").await?;
+//!         assert_eq!("$POBAW123:SERVER:8415", lines.next_line().await?.unwrap());
+//!         assert_eq!("#DPBAW123:1234567", lines.next_line().await?.unwrap());
+//!     }
+//!     Ok::<_, std::io::Error>(())
+//! });
+//!
+//! let config = SessionConfig::pilot(
+//!     Callsign::new("BAW123")?,
+//!     Cid::new("1234567")?,
+//!     "password",
+//!     "Jane Doe",
+//!     PilotRating::Student,
+//!     SimulatorType::Unknown,
+//! );
+//! let mut client = FsdClient::connect("127.0.0.1", port, config).await?;
+//! let mut registrations = 0;
+//! while let Some(event) = client.recv().await {
+//!     match event {
+//!         ClientEvent::Registered { .. } => {
+//!             registrations += 1;
+//!             if registrations == 2 {
+//!                 client.send_text(Callsign::new("EGLL_TWR")?, "Hello")?;
+//!             }
+//!         }
+//!         ClientEvent::Message(FsdMessageType::PingMessage(_)) => client.disconnect(),
+//!         ClientEvent::Closed(reason) => assert_eq!(CloseReason::Disconnected, reason),
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!(2, registrations);
+//! server.await??;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::Instant,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    Callsign, FsdCodec, FsdMessage, FsdMessageType, Recipient,
+    errors::{FsdClientError, FsdError, FsdFramingError},
+    framing::LINE_TERMINATOR,
+    messages::TextMessage,
+    session::{ClientSession, CloseReason, SessionConfig, SessionEvent, SessionState},
+};
+
+/// How [`FsdClient`] retries after its connection drops
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use fsd_interface::client::{ClientEvent, FsdClient, ReconnectPolicy};
+/// use fsd_interface::session::{CloseReason, SessionConfig};
+/// use fsd_interface::{Callsign, Cid, PilotRating, SimulatorType};
+/// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+/// use tokio::net::TcpListener;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // A stand-in server, which drops the first connection once the client has registered
+/// let listener = TcpListener::bind("127.0.0.1:0").await?;
+/// let port = listener.local_addr()?.port();
+/// let server = tokio::spawn(async move {
+///     for connection in 0..2 {
+///         let (stream, _) = listener.accept().await?;
+///         let (reader, mut writer) = stream.into_split();
+///         let mut lines = BufReader::new(reader).lines();
+///         // Give the client time to send something before it has logged in
+///         tokio::time::sleep(Duration::from_millis(50)).await;
+///         writer.write_all(b"$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6\r\n").await?;
+///         assert!(lines.next_line().await?.unwrap().starts_with("$IDBAW123:SERVER:"));
+///         assert!(lines.next_line().await?.unwrap().starts_with("#APBAW123:SERVER:1234567:"));
+///         if connection == 1 {
+///             assert_eq!("#TMBAW123:EGLL_TWR:I'm back", lines.next_line().await?.unwrap());
+///             assert_eq!("#DPBAW123:1234567", lines.next_line().await?.unwrap());
+///         }
+///     }
+///     Ok::<_, std::io::Error>(())
+/// });
+///
+/// let config = SessionConfig::pilot(
+///     Callsign::new("BAW123")?,
+///     Cid::new("1234567")?,
+///     "password",
+///     "Jane Doe",
+///     PilotRating::Student,
+///     SimulatorType::Unknown,
+/// );
+/// let policy = ReconnectPolicy {
+///     initial_delay: Duration::from_millis(10),
+///     ..ReconnectPolicy::default()
+/// };
+/// let mut client = FsdClient::connect_with("127.0.0.1", port, config, policy).await?;
+/// let (mut connections, mut registrations) = (0, 0);
+/// while let Some(event) = client.recv().await {
+///     match event {
+///         ClientEvent::Connected { .. } => {
+///             connections += 1;
+///             // Held until the client has registered again
+///             if connections == 2 {
+///                 client.send_text(Callsign::new("EGLL_TWR")?, "I'm back")?;
+///             }
+///         }
+///         ClientEvent::Reconnecting { attempt, delay } => {
+///             assert_eq!(1, attempt);
+///             assert_eq!(Duration::from_millis(10), delay);
+///         }
+///         ClientEvent::Registered { .. } => {
+///             registrations += 1;
+///             if registrations == 2 {
+///                 client.disconnect();
+///             }
+///         }
+///         ClientEvent::Closed(reason) => assert_eq!(CloseReason::Disconnected, reason),
+///         ClientEvent::Failed(error) => panic!("gave up: {error}"),
+///         _ => {}
+///     }
+/// }
+/// assert_eq!((2, 2), (connections, registrations));
+/// server.await??;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReconnectPolicy {
+    /// The delay before the first attempt. It doubles after every failed attempt.
+    pub initial_delay: Duration,
+    /// The longest delay between two attempts
+    pub max_delay: Duration,
+    /// How many attempts in a row can fail before the client gives up. Unlimited if `None`.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    /// Retries forever, starting after 1 second and backing off to at most a minute
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Never reconnects. The client closes as soon as its connection drops.
+    pub fn never() -> Self {
+        ReconnectPolicy {
+            max_attempts: Some(0),
+            ..ReconnectPolicy::default()
+        }
+    }
+
+    /// The delay before the given attempt, counting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Something received from an [`FsdClient`]
+#[derive(Debug)]
+pub enum ClientEvent {
+    /// A connection was opened, and the login has started
+    Connected { host: String, port: u16 },
+    /// The client registered with the server
+    Registered { server_version: String },
+    /// A message from the server. Every message received is passed on, including those the client has already
+    /// answered itself, such as pings.
+    Message(FsdMessageType),
+    /// A line from the server couldn't be decoded, and was skipped
+    InvalidLine(FsdFramingError),
+    /// The server reported an error that doesn't end the session
+    ServerError(FsdError),
+    /// The connection dropped, or the server didn't respond in time. Another attempt will be made after `delay`.
+    Reconnecting { attempt: u32, delay: Duration },
+    /// The session ended for good. This is the last event.
+    Closed(CloseReason),
+    /// The client stopped because of an error it can't recover from. This is the last event.
+    ///
+    /// This is either the last error seen after [`ReconnectPolicy::max_attempts`] failed attempts, or a
+    /// [`SessionConfig`] holding text that can't be sent.
+    Failed(io::Error),
+}
+
+enum Command {
+    Send(String),
+    Disconnect,
+}
+
+/// A connection to an FSD server, kept alive in a background task
+///
+/// Dropping the client disconnects it.
+#[derive(Debug)]
+pub struct FsdClient {
+    callsign: Callsign,
+    commands: UnboundedSender<Command>,
+    events: UnboundedReceiver<ClientEvent>,
+}
+
+impl FsdClient {
+    /// Connects to `host` and starts logging in, reconnecting according to the default [`ReconnectPolicy`]
+    ///
+    /// Fails only if the first connection can't be opened. Everything after that is reported as a [`ClientEvent`].
+    pub async fn connect(
+        host: impl Into<String>,
+        port: u16,
+        config: SessionConfig,
+    ) -> io::Result<FsdClient> {
+        FsdClient::connect_with(host, port, config, ReconnectPolicy::default()).await
+    }
+
+    /// Connects to `host` and starts logging in, reconnecting according to `policy`
+    pub async fn connect_with(
+        host: impl Into<String>,
+        port: u16,
+        config: SessionConfig,
+        policy: ReconnectPolicy,
+    ) -> io::Result<FsdClient> {
+        let host = host.into();
+        let stream = TcpStream::connect((host.as_str(), port)).await?;
+        let (commands, command_receiver) = mpsc::unbounded_channel();
+        let (event_sender, events) = mpsc::unbounded_channel();
+        let callsign = config.callsign;
+        let connection = Connection {
+            host,
+            port,
+            config,
+            policy,
+            commands: command_receiver,
+            events: event_sender,
+        };
+        tokio::spawn(connection.run(stream));
+        Ok(FsdClient {
+            callsign,
+            commands,
+            events,
+        })
+    }
+
+    pub fn callsign(&self) -> Callsign {
+        self.callsign
+    }
+
+    /// Queues a message to be sent to the server
+    ///
+    /// Messages sent before the client has registered, including after the server has moved it with `$XX`, are
+    /// held and sent once it has. Messages sent while the client is waiting to reconnect after its connection
+    /// dropped are discarded.
+    pub fn send(&self, message: &impl FsdMessage) -> Result<(), FsdClientError> {
+        let line = message.try_encode()?;
+        self.commands
+            .send(Command::Send(line))
+            .map_err(|_| FsdClientError::Closed)
+    }
+
+    /// Sends a text message to a callsign, a frequency, or one of the broadcast recipients
+    pub fn send_text(
+        &self,
+        to: impl Into<Recipient>,
+        message: impl Into<String>,
+    ) -> Result<(), FsdClientError> {
+        self.send(&TextMessage::new(self.callsign, to, message))
+    }
+
+    /// Deregisters from the server and closes the connection. A [`ClientEvent::Closed`] follows once it has.
+    pub fn disconnect(&self) {
+        let _ = self.commands.send(Command::Disconnect);
+    }
+
+    /// The next event, or `None` once the client has closed and every event has been read
+    pub async fn recv(&mut self) -> Option<ClientEvent> {
+        self.events.recv().await
+    }
+}
+
+impl futures_core::Stream for FsdClient {
+    type Item = ClientEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ClientEvent>> {
+        self.get_mut().events.poll_recv(cx)
+    }
+}
+
+/// Why a connection ended
+enum Ended {
+    Dropped(io::Error),
+    ChangeServer(String),
+    Closed(CloseReason),
+    Failed(io::Error),
+}
+
+/// The background task behind an [`FsdClient`]
+struct Connection {
+    host: String,
+    port: u16,
+    config: SessionConfig,
+    policy: ReconnectPolicy,
+    commands: UnboundedReceiver<Command>,
+    events: UnboundedSender<ClientEvent>,
+}
+
+impl Connection {
+    async fn run(mut self, mut stream: TcpStream) {
+        loop {
+            let next = match self.drive(stream).await {
+                Ended::Dropped(error) => self.reconnect(error).await,
+                Ended::ChangeServer(hostname) => {
+                    // Move straight away, only backing off if the new server can't be reached
+                    self.host = hostname;
+                    match TcpStream::connect((self.host.as_str(), self.port)).await {
+                        Ok(stream) => Some(stream),
+                        Err(error) => self.reconnect(error).await,
+                    }
+                }
+                Ended::Closed(reason) => {
+                    self.emit(ClientEvent::Closed(reason));
+                    None
+                }
+                Ended::Failed(error) => {
+                    self.emit(ClientEvent::Failed(error));
+                    None
+                }
+            };
+            match next {
+                Some(next) => stream = next,
+                None => return,
+            }
+        }
+    }
+
+    /// Runs a session over one connection until the connection ends
+    async fn drive(&mut self, stream: TcpStream) -> Ended {
+        self.emit(ClientEvent::Connected {
+            host: self.host.clone(),
+            port: self.port,
+        });
+        let (mut reader, mut writer) = stream.into_split();
+        let mut session = ClientSession::new(self.config.clone(), Instant::now().into_std());
+        let mut codec = FsdCodec::new();
+        let mut incoming = BytesMut::with_capacity(4096);
+        let mut outgoing = BytesMut::new();
+        // Lines sent by the user before the session has registered, which the server would reject
+        let mut held: Vec<String> = Vec::new();
+        loop {
+            while let Some(message) = session.poll_transmit() {
+                if let Err(error) = codec.encode(message, &mut outgoing) {
+                    return Ended::Failed(error);
+                }
+            }
+
+            let mut ended = None;
+            while let Some(event) = session.poll_event() {
+                match event {
+                    SessionEvent::Registered { server_version } => {
+                        for line in held.drain(..) {
+                            push_line(&mut outgoing, &line);
+                        }
+                        self.emit(ClientEvent::Registered { server_version })
+                    }
+                    SessionEvent::Error(error) => self.emit(ClientEvent::ServerError(error)),
+                    SessionEvent::ChangeServer { hostname } => {
+                        ended = Some(Ended::ChangeServer(hostname));
+                    }
+                    SessionEvent::Closed(
+                        CloseReason::HandshakeTimedOut | CloseReason::IdleTimedOut,
+                    ) => ended = Some(Ended::Dropped(ErrorKind::TimedOut.into())),
+                    SessionEvent::Closed(reason) => ended = Some(Ended::Closed(reason)),
+                }
+                if ended.is_some() {
+                    break;
+                }
+            }
+
+            if !outgoing.is_empty() {
+                if let Err(error) = writer.write_all(&outgoing).await {
+                    return Ended::Dropped(error);
+                }
+                outgoing.clear();
+            }
+            if let Some(ended) = ended {
+                return ended;
+            }
+
+            let deadline = session.poll_timeout().map(Instant::from_std);
+            tokio::select! {
+                read = reader.read_buf(&mut incoming) => match read {
+                    Ok(0) => return Ended::Dropped(ErrorKind::UnexpectedEof.into()),
+                    Ok(_) => loop {
+                        match codec.decode(&mut incoming) {
+                            Ok(Some(Ok(message))) => {
+                                session.handle_message(&message, Instant::now().into_std());
+                                self.emit(ClientEvent::Message(message));
+                            }
+                            Ok(Some(Err(error))) => self.emit(ClientEvent::InvalidLine(error)),
+                            Ok(None) => break,
+                            Err(error) => return Ended::Dropped(error),
+                        }
+                    },
+                    Err(error) => return Ended::Dropped(error),
+                },
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    session.handle_timeout(Instant::now().into_std());
+                }
+                command = self.commands.recv() => match command {
+                    Some(Command::Send(line)) => match session.state() {
+                        SessionState::Registered => push_line(&mut outgoing, &line),
+                        _ => held.push(line),
+                    },
+                    Some(Command::Disconnect) | None => session.disconnect(),
+                },
+            }
+        }
+    }
+
+    /// Tries to reconnect with backoff. Returns `None` if the client was closed, or gave up.
+    async fn reconnect(&mut self, mut last_error: io::Error) -> Option<TcpStream> {
+        for attempt in 1.. {
+            if self
+                .policy
+                .max_attempts
+                .is_some_and(|max_attempts| attempt > max_attempts)
+            {
+                break;
+            }
+            let delay = self.policy.delay(attempt);
+            self.emit(ClientEvent::Reconnecting { attempt, delay });
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = wait_for_disconnect(&mut self.commands) => {
+                    self.emit(ClientEvent::Closed(CloseReason::Disconnected));
+                    return None;
+                }
+            }
+            match TcpStream::connect((self.host.as_str(), self.port)).await {
+                Ok(stream) => return Some(stream),
+                Err(error) => last_error = error,
+            }
+        }
+        self.emit(ClientEvent::Failed(last_error));
+        None
+    }
+
+    fn emit(&self, event: ClientEvent) {
+        // The client has been dropped if this fails, and the task will stop at the next command
+        let _ = self.events.send(event);
+    }
+}
+
+fn push_line(outgoing: &mut BytesMut, line: &str) {
+    outgoing.extend_from_slice(line.as_bytes());
+    outgoing.extend_from_slice(LINE_TERMINATOR.as_bytes());
+}
+
+/// Waits until the client disconnects or is dropped, discarding any messages sent in the meantime
+async fn wait_for_disconnect(commands: &mut UnboundedReceiver<Command>) {
+    while let Some(Command::Send(_)) = commands.recv().await {}
+}
@@ -0,0 +1,108 @@
+//! A [tokio-util](https://docs.rs/tokio-util) codec for FSD streams, enabled with the `tokio` feature
+//!
+//! Wrapping a TCP stream in [`Framed`][tokio_util::codec::Framed] with an [`FsdCodec`] gives a
+//! `Stream` of parsed messages and a `Sink` for outgoing [`FsdMessageType`]s.
+//!
+//! A line that cannot be decoded is yielded as an `Err` item rather than as a stream error, so a single
+//! bad line from a peer does not end the stream. The outer `Result` only reports I/O errors.
+//!
+//! # Example
+//! ```
+//! use bytes::BytesMut;
+//! use fsd_interface::{Callsign, FsdCodec, FsdMessageType, Recipient, messages::PingMessage};
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! let mut codec = FsdCodec::new();
+//! let mut buffer = BytesMut::from("$PISERVER:BAW123:8415\r\n$PISERV");
+//! let message = codec.decode(&mut buffer).unwrap().unwrap();
+//! assert!(matches!(message, Ok(FsdMessageType::PingMessage(_))));
+//! assert!(codec.decode(&mut buffer).unwrap().is_none());
+//!
+//! let mut outgoing = BytesMut::new();
+//! let ping = PingMessage::new(Callsign::new("BAW123").unwrap(), Recipient::Server, "8415");
+//! codec.encode(FsdMessageType::PingMessage(ping), &mut outgoing).unwrap();
+//! assert_eq!(&b"$PIBAW123:SERVER:8415\r\n"[..], &outgoing[..]);
+//! ```
+
+use std::fmt::Write;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    FsdMessage, FsdMessageType,
+    errors::{FsdEncodeError, FsdFramingError},
+    framing::{self, DEFAULT_MAX_LINE_LENGTH, Frame, LINE_TERMINATOR, LineSplitter},
+};
+
+/// Decodes FSD lines into [`FsdMessageType`]s and encodes them back, for use with tokio-util's `Framed`.
+///
+/// Encoding a message with a field that would break the line fails with an [`InvalidInput`][std::io::ErrorKind::InvalidInput]
+/// error wrapping an [`FsdEncodeError`], and nothing is written.
+#[derive(Debug, Clone)]
+pub struct FsdCodec {
+    splitter: LineSplitter,
+}
+
+impl Default for FsdCodec {
+    fn default() -> Self {
+        FsdCodec::new()
+    }
+}
+
+impl FsdCodec {
+    /// Creates a new [`FsdCodec`] with a maximum line length of [`DEFAULT_MAX_LINE_LENGTH`]
+    pub fn new() -> Self {
+        FsdCodec::with_max_line_length(DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a new [`FsdCodec`] which rejects lines longer than `max_line_length` bytes, excluding the terminator
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        FsdCodec {
+            splitter: LineSplitter::new(max_line_length),
+        }
+    }
+
+    pub fn max_line_length(&self) -> usize {
+        self.splitter.max_line_length()
+    }
+}
+
+impl Decoder for FsdCodec {
+    type Item = Result<FsdMessageType, FsdFramingError>;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.splitter.next_frame(src) {
+                Some(Frame::Line { len, consumed }) => {
+                    let line = src.split_to(consumed);
+                    return Ok(Some(framing::decode_line(&line[..len])));
+                }
+                Some(Frame::TooLong { consumed }) => {
+                    src.advance(consumed);
+                    return Ok(Some(Err(FsdFramingError::LineTooLong(
+                        self.max_line_length(),
+                    ))));
+                }
+                Some(Frame::Skip { consumed }) => src.advance(consumed),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Encoder<FsdMessageType> for FsdCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: FsdMessageType, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let violations = item.violations();
+        if !violations.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                FsdEncodeError { violations },
+            ));
+        }
+        write!(dst, "{item}{LINE_TERMINATOR}").map_err(std::io::Error::other)
+    }
+}
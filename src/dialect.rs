@@ -0,0 +1,292 @@
+//! The different dialects of the FSD protocol
+//!
+//! The legacy FSD server, VATSIM and IVAO each speak their own version of the protocol. Most
+//! messages are shared, but each dialect has messages the others don't, and some prefixes mean
+//! different things. For example, `#DL` is a server heartbeat on VATSIM but a wind delta on
+//! classic FSD, and IVAO clients add extra fields to their registrations.
+//!
+//! The dialect used for parsing is set in [`ParseOptions`][crate::ParseOptions], and
+//! [`Dialect::encode`] serialises a message for a given dialect. When the dialect of a peer isn't
+//! known in advance, a [`DialectDetector`] can work it out from the first lines of the connection.
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{Callsign, Dialect, FsdMessageType, ParseOptions, parse_message_with};
+//!
+//! let options = ParseOptions {
+//!     dialect: Dialect::Classic,
+//!     ..ParseOptions::strict()
+//! };
+//! let parsed = parse_message_with("#DLSERVER:*:3:-10", &options).unwrap();
+//! assert!(matches!(parsed.message, FsdMessageType::WindDeltaMessage(_)));
+//!
+//! // There is no handshake in classic FSD
+//! let handshake = fsd_interface::messages::InitialServerHandshakeMessage::new(
+//!     Callsign::from_static("SERVER"),
+//!     Callsign::from_static("CLIENT"),
+//!     "VATSIM FSD V3.43",
+//!     "a1b2c3d4e5f6",
+//! );
+//! let handshake = FsdMessageType::InitialServerHandshakeMessage(handshake);
+//! assert!(Dialect::Classic.encode(&handshake).is_none());
+//! assert!(Dialect::Vatsim.encode(&handshake).is_some());
+//!
+//! // IVAO registrations carry the client software, and parse into their own type
+//! let options = ParseOptions {
+//!     dialect: Dialect::Ivao,
+//!     ..ParseOptions::strict()
+//! };
+//! let line = "#APIVA123:SERVER:123456:pw:2:9:11:Joe Bloggs:IvAp:2.0.2";
+//! let parsed = parse_message_with(line, &options).unwrap();
+//! let FsdMessageType::IvaoPilotRegisterMessage(registration) = &parsed.message else {
+//!     panic!("expected an IVAO pilot registration");
+//! };
+//! assert_eq!("IvAp", registration.software);
+//! assert_eq!(line, Dialect::Ivao.encode(&parsed.message).unwrap());
+//!
+//! // IVAO position updates leave off the altitude difference
+//! let line = "@N:IVA123:1200:1:51.47750:-0.46139:100:0:0";
+//! let parsed = parse_message_with(line, &options).unwrap();
+//! assert_eq!(line, Dialect::Ivao.encode(&parsed.message).unwrap());
+//! ```
+
+use crate::{FsdMessageType, ProtocolRevision, prefix::Prefix};
+
+/// A dialect of the FSD protocol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dialect {
+    /// The protocol spoken by VATSIM servers and clients
+    #[default]
+    Vatsim,
+    /// The protocol spoken by the original, open source FSD server. This is what most privately run servers use.
+    ///
+    /// Registrations use the VATSIM field layout, with [`ProtocolRevision::Classic`] as the protocol revision.
+    Classic,
+    /// The protocol spoken by IVAO servers and clients.
+    ///
+    /// IVAO clients append their software name and version to `#AA` and `#AP` registrations, leave the
+    /// altitude difference off `@` position updates, and send client queries that no other dialect has.
+    /// Where an IVAO message carries the same information as a VATSIM one, it is parsed into the same type.
+    Ivao,
+}
+
+impl From<ProtocolRevision> for Dialect {
+    fn from(revision: ProtocolRevision) -> Self {
+        match revision {
+            ProtocolRevision::Classic => Dialect::Classic,
+            ProtocolRevision::VatsimNoAuth
+            | ProtocolRevision::VatsimAuth
+            | ProtocolRevision::Vatsim2022 => Dialect::Vatsim,
+        }
+    }
+}
+
+impl Dialect {
+    /// The protocol revision sent by clients when registering in this dialect, if it is one of the known [`ProtocolRevision`]s
+    pub fn protocol_revision(&self) -> Option<ProtocolRevision> {
+        match self {
+            Dialect::Vatsim => Some(ProtocolRevision::Vatsim2022),
+            Dialect::Classic => Some(ProtocolRevision::Classic),
+            Dialect::Ivao => None,
+        }
+    }
+
+    /// Returns true if the message exists in this dialect
+    pub fn supports(&self, message: &FsdMessageType) -> bool {
+        use FsdMessageType::*;
+        let vatsim_only = matches!(
+            message,
+            InitialServerHandshakeMessage(_)
+                | InitialClientHandshakeMessage(_)
+                | AuthenticationChallengeMessage(_)
+                | AuthenticationResponseMessage(_)
+                | SendFastPositionUpdatesMessage(_)
+                | VelocityPositionStoppedMessage(_)
+                | VelocityPositionSlowMessage(_)
+                | VelocityPositionFastMessage(_)
+                | ChangeServerMessage(_)
+                | ServerHeartbeat
+        );
+        let classic_only = matches!(
+            message,
+            WeatherRequestMessage(_)
+                | TemperatureDataMessage(_)
+                | WindDataMessage(_)
+                | CloudDataMessage(_)
+                | WindDeltaMessage(_)
+        );
+        let ivao_only = matches!(
+            message,
+            IvaoAtcRegisterMessage(_) | IvaoPilotRegisterMessage(_) | IvaoClientQueryMessage(_)
+        );
+        match self {
+            Dialect::Vatsim => !classic_only && !ivao_only,
+            Dialect::Classic => !vatsim_only && !ivao_only,
+            Dialect::Ivao => !vatsim_only && !classic_only,
+        }
+    }
+
+    /// Serialises a message as it is sent in this dialect, without the line terminator.
+    ///
+    /// Returns `None` if the message does not exist in this dialect.
+    pub fn encode(&self, message: &FsdMessageType) -> Option<String> {
+        if !self.supports(message) {
+            return None;
+        }
+        let encoded = match (self, message) {
+            // The legacy server rejects registrations with any other protocol revision
+            (Dialect::Classic, FsdMessageType::AtcRegisterMessage(m)) => {
+                let mut m = m.clone();
+                m.protocol = ProtocolRevision::Classic;
+                m.to_string()
+            }
+            (Dialect::Classic, FsdMessageType::PilotRegisterMessage(m)) => {
+                let mut m = m.clone();
+                m.protocol = ProtocolRevision::Classic;
+                m.to_string()
+            }
+            (Dialect::Ivao, FsdMessageType::PilotPositionUpdateMessage(m)) => {
+                let mut encoded = String::new();
+                // Writing to a String can't fail
+                let _ = m.write_position(&mut encoded);
+                encoded
+            }
+            (_, message) => message.to_string(),
+        };
+        Some(encoded)
+    }
+}
+
+/// The dialect and protocol revision of a connection, as worked out by a [`DialectDetector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetectedDialect {
+    pub dialect: Dialect,
+    /// The protocol revision the client registered with. `None` if the client hasn't registered yet.
+    pub revision: Option<ProtocolRevision>,
+}
+
+/// Works out the dialect of a connection from the first lines sent over it.
+///
+/// Lines are passed to [`DialectDetector::feed`] as they arrive, in either direction. The dialect is decided by:
+/// - a `$ID` client identification with a valid client ID, which is only sent on VATSIM
+/// - the number of fields in a `#AP` or `#AA` registration, as IVAO clients append their software details
+/// - the protocol revision in the registration
+///
+/// A registration is decisive, so once one has been seen, [`DialectDetector::feed`] returns the result.
+/// If no registration arrives within [`DialectDetector::MAX_LINES`] lines, [`DialectDetector::guess`] gives
+/// the best guess from what was seen.
+///
+/// # Example
+/// ```
+/// use fsd_interface::{Dialect, ProtocolRevision, dialect::DialectDetector};
+///
+/// let mut detector = DialectDetector::new();
+/// assert!(detector.feed("$IDBAW123:SERVER:de1e:vPilot:3:8:1234567:123456789:a1b2c3").is_none());
+///
+/// let detected = detector
+///     .feed("#APBAW123:SERVER:1234567::1:101:16:Joe Bloggs")
+///     .unwrap();
+/// assert_eq!(Dialect::Vatsim, detected.dialect);
+/// assert_eq!(Some(ProtocolRevision::Vatsim2022), detected.revision);
+///
+/// // A classic client goes straight to registering
+/// let detected = Dialect::detect(["#AABAW123:SERVER:Joe Bloggs:1234567:pw:1:9"]).unwrap();
+/// assert_eq!(Dialect::Classic, detected.dialect);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DialectDetector {
+    lines: usize,
+    client_id_seen: bool,
+    detected: Option<DetectedDialect>,
+}
+
+impl DialectDetector {
+    /// The number of lines after which a detector stops looking for a registration
+    pub const MAX_LINES: usize = 5;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks at the next line of the connection. Returns the dialect and revision once a registration has been seen.
+    ///
+    /// Lines fed after a result was returned, or after [`DialectDetector::MAX_LINES`] lines, are ignored.
+    pub fn feed(&mut self, line: &str) -> Option<DetectedDialect> {
+        if self.is_done() {
+            return self.detected;
+        }
+        self.lines += 1;
+
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split(':').collect();
+        match Prefix::of(line) {
+            Some(Prefix::ClientHandshake)
+                if fields
+                    .get(2)
+                    .is_some_and(|id| u16::from_str_radix(id, 16).is_ok()) =>
+            {
+                self.client_id_seen = true;
+            }
+            Some(Prefix::PilotRegister) => self.detected = self.registration(&fields, 5, 8),
+            Some(Prefix::AtcRegister) => self.detected = self.registration(&fields, 6, 7),
+            _ => {}
+        }
+        self.detected
+    }
+
+    /// Works out the dialect from a registration with its protocol revision at `revision_index`
+    /// and `vatsim_fields` fields in VATSIM and classic FSD. IVAO registrations add two more.
+    fn registration(
+        &self,
+        fields: &[&str],
+        revision_index: usize,
+        vatsim_fields: usize,
+    ) -> Option<DetectedDialect> {
+        let revision = fields
+            .get(revision_index)?
+            .parse::<ProtocolRevision>()
+            .ok()?;
+        let dialect = if fields.len() >= vatsim_fields + 2 {
+            Dialect::Ivao
+        } else if self.client_id_seen {
+            Dialect::Vatsim
+        } else {
+            Dialect::from(revision)
+        };
+        Some(DetectedDialect {
+            dialect,
+            revision: Some(revision),
+        })
+    }
+
+    /// Returns true once the dialect has been detected, or the detector has given up looking
+    pub fn is_done(&self) -> bool {
+        self.detected.is_some() || self.lines >= Self::MAX_LINES
+    }
+
+    /// The best guess at the dialect from the lines seen so far.
+    ///
+    /// If no registration has been seen, a client identification means VATSIM and no revision is known.
+    pub fn guess(&self) -> Option<DetectedDialect> {
+        self.detected
+            .or(self.client_id_seen.then_some(DetectedDialect {
+                dialect: Dialect::Vatsim,
+                revision: None,
+            }))
+    }
+}
+
+impl Dialect {
+    /// Detects the dialect from the first lines of a connection. See [`DialectDetector`].
+    pub fn detect<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<DetectedDialect> {
+        let mut detector = DialectDetector::new();
+        for line in lines {
+            detector.feed(line);
+            if detector.is_done() {
+                break;
+            }
+        }
+        detector.guess()
+    }
+}
@@ -1,10 +1,17 @@
 use std::net::Ipv4Addr;
-use std::{fmt::Display, str::FromStr};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
 
 use crate::messages::*;
+use crate::options::{ParseContext, ParseWarning};
+use crate::prefix::{self, Prefix};
 use crate::structs::{RadioFrequency, TransponderCode};
-use crate::{aircraft_config::AircraftConfig, errors::FsdMessageParseError};
-use chrono::{DateTime, Utc};
+use crate::validation::FieldChecker;
+use crate::{
+    aircraft_config::AircraftConfig,
+    errors::{FieldViolation, FsdMessageParseError},
+};
+use crate::{identifiers::Callsign, Dialect};
+use chrono::{DateTime, NaiveTime, Utc};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ClientCapability {
@@ -33,7 +40,7 @@ pub enum ClientCapability {
 }
 impl ClientCapability {
     pub fn is_known(&self) -> bool {
-        return !matches!(self, ClientCapability::Unknown(_));
+        !matches!(self, ClientCapability::Unknown(_))
     }
 }
 impl<S: AsRef<str>> From<S> for ClientCapability {
@@ -95,7 +102,22 @@ impl Display for ClientCapability {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Serialised as it is sent in FSD, for example `"VISUPDATE"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClientCapability {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ClientCapability {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ClientCapability::from(String::deserialize(deserializer)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AtcRating {
     Observer = 1,
     S1,
@@ -133,7 +155,8 @@ impl FromStr for AtcRating {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PilotRating {
     Student = 1,
     VFR,
@@ -159,7 +182,8 @@ impl FromStr for PilotRating {
 }
 
 /// Represents a version of the FSD protocol
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProtocolRevision {
     /// Used on legacy FSD servers. If the FSD server is a privately run one, it is most likely using this version
     Classic = 9,
@@ -183,7 +207,8 @@ impl FromStr for ProtocolRevision {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SimulatorType {
     Unknown,
     MSFS95,
@@ -239,7 +264,8 @@ impl<S: AsRef<str>> From<S> for SimulatorType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FlightRules {
     DVFR,
     SVFR,
@@ -272,7 +298,8 @@ impl Display for FlightRules {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AtcType {
     Observer,
     FlightServiceStation,
@@ -290,7 +317,7 @@ impl AtcType {
             AtcType::Delivery | AtcType::Ground => 20,
             AtcType::Tower => 50,
             AtcType::Approach => 150,
-            AtcType::Centre => 600
+            AtcType::Centre => 600,
         }
     }
 }
@@ -327,7 +354,8 @@ impl Display for AtcType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TransponderMode {
     Standby,
     ModeC,
@@ -354,7 +382,12 @@ impl Display for TransponderMode {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum FsdMessageType {
     AtcRegisterMessage(AtcRegisterMessage),
     PilotRegisterMessage(PilotRegisterMessage),
@@ -384,176 +417,342 @@ pub enum FsdMessageType {
     FsdErrorMessage(FsdErrorMessage),
     FlightPlanMessage(FlightPlanMessage),
     FlightPlanAmendmentMessage(FlightPlanAmendmentMessage),
-    FSInnPlaneInformationRequestMessage,  // Deprecated
-    FSInnPlaneInformationResponseMessage, // Deprecated
+    FSInnPlaneInformationRequestMessage(FSInnPlaneInformationRequestMessage), // Deprecated
+    FSInnPlaneInformationResponseMessage(FSInnPlaneInformationResponseMessage), // Deprecated
     ServerHeartbeat,
     ClientQueryMessage(ClientQueryMessage),
     ClientQueryResponseMessage(ClientQueryResponseMessage),
     HandoffOfferMessage(HandoffOfferMessage),
     HandoffAcceptMessage(HandoffAcceptMessage),
     SharedStateMessage(SharedStateMessage),
+    WeatherRequestMessage(WeatherRequestMessage),
+    TemperatureDataMessage(TemperatureDataMessage),
+    WindDataMessage(WindDataMessage),
+    CloudDataMessage(CloudDataMessage),
+    WindDeltaMessage(WindDeltaMessage),
+    IvaoAtcRegisterMessage(IvaoAtcRegisterMessage),
+    IvaoPilotRegisterMessage(IvaoPilotRegisterMessage),
+    IvaoClientQueryMessage(IvaoClientQueryMessage),
+    /// A message that is not recognised, kept as-is so that it can be forwarded.
+    /// Displays exactly as it was received.
+    ///
+    /// # Example
+    /// ```
+    /// use fsd_interface::FsdMessageType;
+    /// let line = "$ZZEGLL_TWR:BAW123:some:data";
+    /// let message = fsd_interface::parse_message(line).unwrap();
+    /// assert!(matches!(&message, FsdMessageType::Unknown { prefix, .. } if prefix == "$ZZ"));
+    /// assert_eq!(line, message.to_string());
+    /// ```
+    Unknown {
+        prefix: String,
+        from: String,
+        to: Option<String>,
+        fields: Vec<String>,
+    },
+}
+
+/// The type of an [`FsdMessageType`], without the message itself. Returned by [`FsdMessage::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MessageKind {
+    AtcRegister,
+    PilotRegister,
+    AtcDeregister,
+    PilotDeregister,
+    AtcPositionUpdate,
+    AtcSecondaryVisCentre,
+    PilotPositionUpdate,
+    AuthenticationChallenge,
+    AuthenticationResponse,
+    Text,
+    Frequency,
+    ChangeServer,
+    InitialServerHandshake,
+    InitialClientHandshake,
+    SendFastPositionUpdates,
+    VelocityPositionStopped,
+    VelocityPositionSlow,
+    VelocityPositionFast,
+    Kill,
+    MetarRequest,
+    MetarResponse,
+    Ping,
+    Pong,
+    PlaneInfoRequest,
+    PlaneInfoResponse,
+    FsdError,
+    FlightPlan,
+    FlightPlanAmendment,
+    FSInnPlaneInformationRequest,
+    FSInnPlaneInformationResponse,
+    ServerHeartbeat,
+    ClientQuery,
+    ClientQueryResponse,
+    HandoffOffer,
+    HandoffAccept,
+    SharedState,
+    WeatherRequest,
+    TemperatureData,
+    WindData,
+    CloudData,
+    WindDelta,
+    IvaoAtcRegister,
+    IvaoPilotRegister,
+    IvaoClientQuery,
+    Unknown,
 }
 
 impl FsdMessageType {
-    pub(crate) fn identify(message: &str) -> Result<FsdMessageType, FsdMessageParseError> {
-        let fields: Vec<&str> = message.split(':').collect();
-        if fields.len() < 2 {
-            if fields[0].starts_with("#DA") {
-                return Ok(Self::AtcDeregisterMessage(fields.as_slice().try_into()?));
-            }
-            if fields[0].starts_with("#DP") {
-                return Ok(Self::PilotDeregisterMessage(fields.as_slice().try_into()?));
+    pub(crate) fn identify(
+        message: &str,
+        cx: &mut ParseContext,
+    ) -> Result<FsdMessageType, FsdMessageParseError> {
+        // Read the prefix before splitting, so that `#TM` text keeps its colons and position updates are split
+        // without allocating. Apart from `#TM` text, lines are split on all of their colons up front, as the
+        // parsers read the fields by index.
+        let prefix = Prefix::of(message);
+        let fields = match prefix {
+            Some(prefix) => prefix.split(message),
+            None => prefix::Fields::Heap(prefix::split_fields(message)),
+        };
+        let result = prefix
+            .ok_or_else(|| FsdMessageParseError::UnknownMessageType(message.to_string()))
+            .and_then(|prefix| Self::identify_fields(message, prefix, &fields, cx))
+            .and_then(|m| {
+                if cx.dialect().supports(&m) {
+                    Ok(m)
+                } else {
+                    Err(FsdMessageParseError::UnknownMessageType(
+                        message.to_string(),
+                    ))
+                }
+            });
+        match result {
+            Err(e) if matches!(e.inner(), FsdMessageParseError::UnknownMessageType(_)) => {
+                cx.deviation(ParseWarning::UnknownMessage, e)?;
+                Ok(Self::unknown(&fields))
             }
-            return Err(FsdMessageParseError::UnknownMessageType(
-                message.to_string(),
-            ));
-        }
-        if fields[0].starts_with("#DA") {
-            return Ok(Self::AtcDeregisterMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("#DP") {
-            return Ok(Self::PilotDeregisterMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("#AA") {
-            return Ok(Self::AtcRegisterMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("#AP") {
-            return Ok(Self::PilotRegisterMessage(fields.as_slice().try_into()?));
+            result => result.map_err(|e| e.in_line(&fields)),
         }
+    }
 
-        if fields[0].starts_with('%') {
-            return Ok(Self::AtcPositionUpdateMessage(
-                fields.as_slice().try_into()?,
-            ));
+    fn unknown(fields: &[&str]) -> FsdMessageType {
+        let first = fields[0];
+        // '#' and '$' are followed by two more characters. Other prefixes are a single character.
+        let prefix_len = match first.chars().next() {
+            Some('#' | '$') => first.char_indices().nth(3).map_or(first.len(), |(i, _)| i),
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+        FsdMessageType::Unknown {
+            prefix: first[..prefix_len].to_string(),
+            from: first[prefix_len..].to_string(),
+            to: fields.get(1).map(|to| to.to_string()),
+            fields: fields
+                .iter()
+                .skip(2)
+                .map(|field| field.to_string())
+                .collect(),
         }
-        if fields[0].starts_with('\'') {
-            return Ok(Self::AtcSecondaryVisCentreMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with('@') {
-            return Ok(Self::PilotPositionUpdateMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$ZC") {
-            return Ok(Self::AuthenticationChallengeMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$ZR") {
-            return Ok(Self::AuthenticationResponseMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$ER") {
-            return Ok(Self::FsdErrorMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$HO") {
-            return Ok(Self::HandoffOfferMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$HA") {
-            return Ok(Self::HandoffAcceptMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("#TM") {
-            if fields[1].starts_with('@') {
-                return Ok(Self::FrequencyMessage(fields.as_slice().try_into()?));
+    }
+
+    fn identify_fields(
+        message: &str,
+        prefix: Prefix,
+        fields: &[&str],
+        cx: &mut ParseContext,
+    ) -> Result<FsdMessageType, FsdMessageParseError> {
+        let unknown = || FsdMessageParseError::UnknownMessageType(message.to_string());
+        // Deregistrations are the only messages that can be a single field
+        if fields.len() < 2 && !matches!(prefix, Prefix::AtcDeregister | Prefix::PilotDeregister) {
+            return Err(unknown());
+        }
+        let ivao = cx.dialect() == Dialect::Ivao;
+        let message = match prefix {
+            Prefix::AtcDeregister => Self::AtcDeregisterMessage(cx.parse(fields)?),
+            Prefix::PilotDeregister => Self::PilotDeregisterMessage(cx.parse(fields)?),
+            // IVAO clients append their software name and version to registrations
+            Prefix::AtcRegister if ivao && fields.len() > 8 => {
+                Self::IvaoAtcRegisterMessage(cx.parse(fields)?)
+            }
+            Prefix::AtcRegister => Self::AtcRegisterMessage(cx.parse(fields)?),
+            Prefix::PilotRegister if ivao && fields.len() > 9 => {
+                Self::IvaoPilotRegisterMessage(cx.parse(fields)?)
+            }
+            Prefix::PilotRegister => Self::PilotRegisterMessage(cx.parse(fields)?),
+            Prefix::AtcPosition => Self::AtcPositionUpdateMessage(cx.parse(fields)?),
+            Prefix::SecondaryVisCentre => Self::AtcSecondaryVisCentreMessage(cx.parse(fields)?),
+            Prefix::PilotPosition => Self::PilotPositionUpdateMessage(cx.parse(fields)?),
+            Prefix::AuthenticationChallenge => {
+                Self::AuthenticationChallengeMessage(cx.parse(fields)?)
             }
+            Prefix::AuthenticationResponse => {
+                Self::AuthenticationResponseMessage(cx.parse(fields)?)
+            }
+            Prefix::Error => Self::FsdErrorMessage(cx.parse(fields)?),
+            Prefix::HandoffOffer => Self::HandoffOfferMessage(cx.parse(fields)?),
+            Prefix::HandoffAccept => Self::HandoffAcceptMessage(cx.parse(fields)?),
+            Prefix::Text if fields[1].starts_with('@') => Self::FrequencyMessage(cx.parse(fields)?),
+            Prefix::Text => Self::TextMessage(cx.parse(fields)?),
+            Prefix::ChangeServer => Self::ChangeServerMessage(cx.parse(fields)?),
+            Prefix::FlightPlan => Self::FlightPlanMessage(cx.parse(fields)?),
+            Prefix::FlightPlanAmendment => Self::FlightPlanAmendmentMessage(cx.parse(fields)?),
+            Prefix::ServerHandshake => Self::InitialServerHandshakeMessage(cx.parse(fields)?),
+            Prefix::ClientHandshake => Self::InitialClientHandshakeMessage(cx.parse(fields)?),
+            Prefix::SendFastPositions => Self::SendFastPositionUpdatesMessage(cx.parse(fields)?),
+            Prefix::VelocityStopped => Self::VelocityPositionStoppedMessage(cx.parse(fields)?),
+            Prefix::Heartbeat if cx.dialect() == Dialect::Classic => {
+                Self::WindDeltaMessage(cx.parse(fields)?)
+            }
+            Prefix::Heartbeat => Self::ServerHeartbeat,
+            Prefix::WeatherRequest => Self::WeatherRequestMessage(cx.parse(fields)?),
+            Prefix::TemperatureData => Self::TemperatureDataMessage(cx.parse(fields)?),
+            Prefix::WindData => Self::WindDataMessage(cx.parse(fields)?),
+            Prefix::CloudData => Self::CloudDataMessage(cx.parse(fields)?),
+            Prefix::VelocitySlow => Self::VelocityPositionSlowMessage(cx.parse(fields)?),
+            Prefix::SharedState => Self::SharedStateMessage(cx.parse(fields)?),
+            Prefix::VelocityFast => Self::VelocityPositionFastMessage(cx.parse(fields)?),
+            Prefix::Kill => Self::KillMessage(cx.parse(fields)?),
+            Prefix::MetarRequest => Self::MetarRequestMessage(cx.parse(fields)?),
+            Prefix::MetarResponse => Self::MetarResponseMessage(cx.parse(fields)?),
+            Prefix::ClientQuery => match cx.parse::<ClientQueryMessage>(fields) {
+                Err(e)
+                    if ivao && matches!(e.inner(), FsdMessageParseError::UnknownMessageType(_)) =>
+                {
+                    Self::IvaoClientQueryMessage(cx.parse(fields)?)
+                }
+                result => Self::ClientQueryMessage(result?),
+            },
+            Prefix::ClientQueryResponse => Self::ClientQueryResponseMessage(cx.parse(fields)?),
+            Prefix::Ping => Self::PingMessage(cx.parse(fields)?),
+            Prefix::Pong => Self::PongMessage(cx.parse(fields)?),
+            Prefix::SquawkBox => match fields.get(2) {
+                Some(&"PIR") => Self::PlaneInfoRequestMessage(cx.parse(fields)?),
+                Some(&"PI") => Self::PlaneInfoResponseMessage(cx.parse(fields)?),
+                Some(&"FSIPI") => Self::FSInnPlaneInformationResponseMessage(cx.parse(fields)?),
+                Some(&"FSIPIR") => Self::FSInnPlaneInformationRequestMessage(cx.parse(fields)?),
+                _ => return Err(unknown()),
+            },
+        };
+        Ok(message)
+    }
+}
+impl FsdMessageType {
+    /// The message held by this variant. `None` for [`FsdMessageType::ServerHeartbeat`] and [`FsdMessageType::Unknown`],
+    /// which don't hold a message struct.
+    fn message(&self) -> Option<&dyn FsdMessage> {
+        let message: &dyn FsdMessage = match self {
+            FsdMessageType::AtcRegisterMessage(m) => m,
+            FsdMessageType::PilotRegisterMessage(m) => m,
+            FsdMessageType::AtcDeregisterMessage(m) => m,
+            FsdMessageType::PilotDeregisterMessage(m) => m,
+            FsdMessageType::AtcPositionUpdateMessage(m) => m,
+            FsdMessageType::AtcSecondaryVisCentreMessage(m) => m,
+            FsdMessageType::PilotPositionUpdateMessage(m) => m,
+            FsdMessageType::AuthenticationChallengeMessage(m) => m,
+            FsdMessageType::AuthenticationResponseMessage(m) => m,
+            FsdMessageType::TextMessage(m) => m,
+            FsdMessageType::FrequencyMessage(m) => m,
+            FsdMessageType::ChangeServerMessage(m) => m,
+            FsdMessageType::InitialServerHandshakeMessage(m) => m,
+            FsdMessageType::InitialClientHandshakeMessage(m) => m,
+            FsdMessageType::SendFastPositionUpdatesMessage(m) => m,
+            FsdMessageType::VelocityPositionStoppedMessage(m) => m,
+            FsdMessageType::VelocityPositionSlowMessage(m) => m,
+            FsdMessageType::VelocityPositionFastMessage(m) => m,
+            FsdMessageType::KillMessage(m) => m,
+            FsdMessageType::MetarRequestMessage(m) => m,
+            FsdMessageType::MetarResponseMessage(m) => m,
+            FsdMessageType::PingMessage(m) => m,
+            FsdMessageType::PongMessage(m) => m,
+            FsdMessageType::PlaneInfoRequestMessage(m) => m,
+            FsdMessageType::PlaneInfoResponseMessage(m) => m,
+            FsdMessageType::FsdErrorMessage(m) => m,
+            FsdMessageType::FlightPlanMessage(m) => m,
+            FsdMessageType::FlightPlanAmendmentMessage(m) => m,
+            FsdMessageType::FSInnPlaneInformationRequestMessage(m) => m,
+            FsdMessageType::FSInnPlaneInformationResponseMessage(m) => m,
+            FsdMessageType::ClientQueryMessage(m) => m,
+            FsdMessageType::ClientQueryResponseMessage(m) => m,
+            FsdMessageType::HandoffOfferMessage(m) => m,
+            FsdMessageType::HandoffAcceptMessage(m) => m,
+            FsdMessageType::SharedStateMessage(m) => m,
+            FsdMessageType::WeatherRequestMessage(m) => m,
+            FsdMessageType::TemperatureDataMessage(m) => m,
+            FsdMessageType::WindDataMessage(m) => m,
+            FsdMessageType::CloudDataMessage(m) => m,
+            FsdMessageType::WindDeltaMessage(m) => m,
+            FsdMessageType::IvaoAtcRegisterMessage(m) => m,
+            FsdMessageType::IvaoPilotRegisterMessage(m) => m,
+            FsdMessageType::IvaoClientQueryMessage(m) => m,
+            FsdMessageType::ServerHeartbeat | FsdMessageType::Unknown { .. } => return None,
+        };
+        Some(message)
+    }
+}
 
-            return Ok(Self::TextMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$XX") {
-            return Ok(Self::ChangeServerMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$FP") {
-            return Ok(Self::FlightPlanMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$AM") {
-            return Ok(Self::FlightPlanAmendmentMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$DI") {
-            return Ok(Self::InitialServerHandshakeMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$ID") {
-            return Ok(Self::InitialClientHandshakeMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$SF") {
-            return Ok(Self::SendFastPositionUpdatesMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("#ST") {
-            return Ok(Self::VelocityPositionStoppedMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("#DL") {
-            return Ok(Self::ServerHeartbeat);
-        }
-        if fields[0].starts_with("#SL") {
-            return Ok(Self::VelocityPositionSlowMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("#PC") {
-            return Ok(Self::SharedStateMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with('^') {
-            return Ok(Self::VelocityPositionFastMessage(
-                fields.as_slice().try_into()?,
-            ));
-        }
-        if fields[0].starts_with("$!!") {
-            return Ok(Self::KillMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$AX") {
-            return Ok(Self::MetarRequestMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$AR") {
-            return Ok(Self::MetarResponseMessage(fields.as_slice().try_into()?));
-        }
-        if fields[0].starts_with("$CQ") {
-            return Ok(Self::ClientQueryMessage(fields.as_slice().try_into()?));
+impl FsdMessage for FsdMessageType {
+    fn sender(&self) -> &str {
+        match self {
+            FsdMessageType::ServerHeartbeat => SERVER_CALLSIGN,
+            FsdMessageType::Unknown { from, .. } => from,
+            message => message.message().map_or("", |m| m.sender()),
         }
-        if fields[0].starts_with("$CR") {
-            return Ok(Self::ClientQueryResponseMessage(
-                fields.as_slice().try_into()?,
-            ));
+    }
+    fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+        match self {
+            FsdMessageType::ServerHeartbeat => Some(Cow::Owned(Recipient::Broadcast)),
+            FsdMessageType::Unknown { to, .. } => to
+                .as_deref()
+                .and_then(|to| {
+                    to.parse()
+                        .ok()
+                        .or_else(|| Callsign::lenient(to).map(Recipient::Callsign))
+                })
+                .map(Cow::Owned),
+            message => message.message().and_then(|m| m.recipient()),
         }
-        if fields[0].starts_with("$PI") {
-            return Ok(Self::PingMessage(fields.as_slice().try_into()?));
+    }
+    fn prefix(&self) -> &str {
+        match self {
+            FsdMessageType::ServerHeartbeat => "#DL",
+            FsdMessageType::Unknown { prefix, .. } => prefix,
+            message => message.message().map_or("", |m| m.prefix()),
         }
-        if fields[0].starts_with("$PO") {
-            return Ok(Self::PongMessage(fields.as_slice().try_into()?));
+    }
+    fn kind(&self) -> MessageKind {
+        match self {
+            FsdMessageType::ServerHeartbeat => MessageKind::ServerHeartbeat,
+            FsdMessageType::Unknown { .. } => MessageKind::Unknown,
+            message => message.message().map_or(MessageKind::Unknown, |m| m.kind()),
         }
-        if fields[0].starts_with("#SB") {
-            if fields[2] == "PIR" {
-                return Ok(Self::PlaneInfoRequestMessage(fields.as_slice().try_into()?));
-            }
-            if fields[2] == "PI" {
-                return Ok(Self::PlaneInfoResponseMessage(
-                    fields.as_slice().try_into()?,
-                ));
-            }
-            if fields[2] == "FSIPI" {
-                return Ok(Self::FSInnPlaneInformationResponseMessage);
-            }
-            if fields[2] == "FSIPIR" {
-                return Ok(Self::FSInnPlaneInformationRequestMessage);
+    }
+    fn violations(&self) -> Vec<FieldViolation> {
+        match self {
+            FsdMessageType::ServerHeartbeat => Vec::new(),
+            FsdMessageType::Unknown {
+                prefix,
+                from,
+                to,
+                fields,
+            } => {
+                let mut check = FieldChecker::default();
+                check.field("prefix", prefix);
+                check.field("from", from);
+                if let Some(to) = to {
+                    check.field("to", to);
+                }
+                for field in fields {
+                    check.field("fields", field);
+                }
+                check.finish()
             }
+            message => message.message().map_or_else(Vec::new, |m| m.violations()),
         }
-
-        Err(FsdMessageParseError::UnknownMessageType(
-            message.to_string(),
-        ))
     }
 }
+
 impl Display for FsdMessageType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -585,23 +784,51 @@ impl Display for FsdMessageType {
             FsdMessageType::FsdErrorMessage(m) => m.fmt(f),
             FsdMessageType::FlightPlanMessage(m) => m.fmt(f),
             FsdMessageType::FlightPlanAmendmentMessage(m) => m.fmt(f),
-            m @ FsdMessageType::FSInnPlaneInformationRequestMessage => m.fmt(f),
-            m @ FsdMessageType::FSInnPlaneInformationResponseMessage => m.fmt(f),
-            m @ FsdMessageType::ServerHeartbeat => m.fmt(f),
+            FsdMessageType::FSInnPlaneInformationRequestMessage(m) => m.fmt(f),
+            FsdMessageType::FSInnPlaneInformationResponseMessage(m) => m.fmt(f),
+            FsdMessageType::ServerHeartbeat => write!(f, "#DL{SERVER_CALLSIGN}:*:0:0"),
             FsdMessageType::ClientQueryMessage(m) => m.fmt(f),
             FsdMessageType::ClientQueryResponseMessage(m) => m.fmt(f),
             FsdMessageType::HandoffOfferMessage(m) => m.fmt(f),
             FsdMessageType::HandoffAcceptMessage(m) => m.fmt(f),
             FsdMessageType::SharedStateMessage(m) => m.fmt(f),
+            FsdMessageType::WeatherRequestMessage(m) => m.fmt(f),
+            FsdMessageType::TemperatureDataMessage(m) => m.fmt(f),
+            FsdMessageType::WindDataMessage(m) => m.fmt(f),
+            FsdMessageType::CloudDataMessage(m) => m.fmt(f),
+            FsdMessageType::WindDeltaMessage(m) => m.fmt(f),
+            FsdMessageType::IvaoAtcRegisterMessage(m) => m.fmt(f),
+            FsdMessageType::IvaoPilotRegisterMessage(m) => m.fmt(f),
+            FsdMessageType::IvaoClientQueryMessage(m) => m.fmt(f),
+            FsdMessageType::Unknown {
+                prefix,
+                from,
+                to,
+                fields,
+            } => {
+                write!(f, "{prefix}{from}")?;
+                if let Some(to) = to {
+                    write!(f, ":{to}")?;
+                }
+                for field in fields {
+                    write!(f, ":{field}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 #[allow(unused)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum ClientQueryType {
     IsValidATC {
-        atc_callsign: String,
+        atc_callsign: Callsign,
     }, //ATC
     Capabilities, //CAPS
     Com1Freq,     //C?
@@ -611,7 +838,7 @@ pub enum ClientQueryType {
     PublicIP,     //IP
     INF,          //INF
     FlightPlan {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     }, //FP
     ForceBeaconCode {
         code: TransponderCode,
@@ -625,36 +852,36 @@ pub enum ClientQueryType {
         message: Option<String>,
     }, //NOHLP
     WhoHas {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     }, //WH
     InitiateTrack {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     }, //IT
     AcceptHandoff {
-        aircraft_callsign: String,
-        atc_callsign: String,
+        aircraft_callsign: Callsign,
+        atc_callsign: Callsign,
     }, //HT
     DropTrack {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     }, //DR
     SetFinalAltitude {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         level: Level,
     }, //FA
     SetTempAltitude {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         level: Level,
     }, //TA
     SetBeaconCode {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         code: TransponderCode,
     }, //BC
     SetScratchpad {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         contents: ScratchPad,
     }, //SC
     SetVoiceType {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         voice_capability: VoiceCapability,
     }, //VT
     AircraftConfigurationRequest, //ACC
@@ -672,9 +899,15 @@ pub enum ClientQueryType {
         surface_wind: String,
         pressure: String,
     }, //NEWATIS
-    //Estimate,                                                                     //EST
+    /// An estimate of when an aircraft will reach a fix, and at what level, passed between controllers
+    Estimate {
+        aircraft_callsign: Callsign,
+        fix: String,
+        time: NaiveTime,
+        level: Level,
+    }, //EST
     SetGlobalData {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         contents: String,
     }, //GD
 }
@@ -770,12 +1003,32 @@ impl Display for ClientQueryType {
             } => {
                 write!(f, "GD:{}:{}", aircraft_callsign, contents)
             }
+            ClientQueryType::Estimate {
+                aircraft_callsign,
+                fix,
+                time,
+                level,
+            } => {
+                write!(
+                    f,
+                    "EST:{}:{}:{}:{}",
+                    aircraft_callsign,
+                    fix,
+                    time.format("%H%M"),
+                    level
+                )
+            }
         }
     }
 }
 
 #[allow(unused)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum AtisLine {
     VoiceServer(String),
     TextLine(String),
@@ -798,7 +1051,12 @@ impl Display for AtisLine {
 }
 
 #[allow(unused)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum ClientResponseType {
     Com1Freq {
         frequency: RadioFrequency,
@@ -821,7 +1079,7 @@ pub enum ClientResponseType {
         hostname_or_ip_address: String,
     },
     IsValidATC {
-        atc_callsign: String,
+        atc_callsign: Callsign,
         valid_atc: bool,
     },
 }
@@ -829,7 +1087,8 @@ impl Display for ClientResponseType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ClientResponseType::Com1Freq { frequency } => {
-                write!(f, "C?:{}", frequency.to_human_readable_string())
+                let (left, right) = frequency.frequency();
+                write!(f, "C?:{left}.{right:03}")
             }
             ClientResponseType::ATIS { atis_line } => write!(f, "ATIS:{}", atis_line),
             ClientResponseType::RealName {
@@ -866,54 +1125,59 @@ impl Display for ClientResponseType {
 }
 
 #[allow(unused)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum SharedStateType {
     Version,
     ID,
     DI,
     IHave {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     },
     ScratchPad {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         contents: ScratchPad,
     },
     TempAltitude {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         level: Level,
     },
     FinalAltitude {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         level: Level,
     },
     VoiceType {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         voice_capability: VoiceCapability,
     },
     BeaconCode {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         code: TransponderCode,
     },
     HandoffCancel {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     },
     FlightStrip {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         format: Option<i32>,
         contents: Option<Vec<String>>,
     },
     PushToDepartureList {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     },
     PointOut {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
     },
     LandLine {
         landline_type: LandLineType,
         landline_command: LandLineCommand,
     },
     GlobalData {
-        aircraft_callsign: String,
+        aircraft_callsign: Callsign,
         contents: String,
     },
 }
@@ -1017,14 +1281,20 @@ impl Display for SharedStateType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LandLineType {
     Intercom,
     Override,
     Monitor,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum LandLineCommand {
     Request { ip_address: Ipv4Addr, port: u16 },
     Approve { ip_address: Ipv4Addr, port: u16 },
@@ -1032,7 +1302,8 @@ pub enum LandLineCommand {
     End,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operator {
     Exactly,
     OrLess,
@@ -1048,7 +1319,8 @@ impl Display for Operator {
     }
 }
 
-#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GroundState {
     #[default]
     NoState,
@@ -1079,7 +1351,12 @@ impl Display for GroundState {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum ScratchPad {
     PlainTextOrDirect(String),
     RateOfClimbDescent(u32),
@@ -1176,7 +1453,8 @@ impl Display for ScratchPad {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VoiceCapability {
     #[default]
     Voice,
@@ -1189,7 +1467,18 @@ impl<S: AsRef<str>> From<S> for VoiceCapability {
         match value.to_lowercase().as_str() {
             "t" => VoiceCapability::Text,
             "r" => VoiceCapability::Receive,
-            _ => VoiceCapability::default()
+            _ => VoiceCapability::default(),
+        }
+    }
+}
+impl FromStr for VoiceCapability {
+    type Err = FsdMessageParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "v" => Ok(VoiceCapability::Voice),
+            "t" => Ok(VoiceCapability::Text),
+            "r" => Ok(VoiceCapability::Receive),
+            _ => Err(FsdMessageParseError::InvalidVoiceCapability(s.to_string())),
         }
     }
 }
@@ -1208,7 +1497,12 @@ impl Display for VoiceCapability {
 /// filed or cleared flight level, altitude or special VFR keyword
 ///
 /// Currently no difference in behaviour for Altitude vs FL, needs testing of other clients
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum Level {
     // Used for example when filing with the "VFR" keyword
     VFR,
@@ -1225,17 +1519,22 @@ impl FromStr for Level {
         } else if input == "VFR" {
             Ok(Self::VFR)
         } else {
-            let flight_level = input.to_uppercase().starts_with("FL");
+            let flight_level = input
+                .get(..2)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("FL"));
             let input_trimmed = if flight_level { &input[2..] } else { input };
 
             let as_num: i32 = input_trimmed
                 .parse()
                 .map_err(|_| FsdMessageParseError::InvalidLevel(input.to_string()))?;
-            Ok(if flight_level {
-                Self::FlightLevel(as_num * 100)
+            if flight_level {
+                as_num
+                    .checked_mul(100)
+                    .map(Self::FlightLevel)
+                    .ok_or_else(|| FsdMessageParseError::InvalidLevel(input.to_string()))
             } else {
-                Self::FlightLevel(as_num)
-            })
+                Ok(Self::FlightLevel(as_num))
+            }
         }
     }
 }
@@ -1248,3 +1547,140 @@ impl Display for Level {
         }
     }
 }
+
+/// Who a message is addressed to
+///
+/// # Example
+/// ```
+/// use fsd_interface::{Callsign, RadioFrequency, Recipient};
+///
+/// assert_eq!(Recipient::AllAtc, "*A".parse().unwrap());
+/// assert_eq!(
+///     Recipient::Frequencies(vec![RadioFrequency::new(122, 800).unwrap()]),
+///     "@22800".parse().unwrap()
+/// );
+/// assert_eq!(
+///     Recipient::Callsign(Callsign::new("BAW123").unwrap()),
+///     "baw123".parse().unwrap()
+/// );
+///
+/// // Frequencies must be valid
+/// assert!("@99999".parse::<Recipient>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+pub enum Recipient {
+    /// The server itself, `SERVER`
+    Server,
+    /// Every client in range, `*`
+    Broadcast,
+    /// Every ATC client, `*A`
+    AllAtc,
+    /// Every pilot client, `*P`
+    AllPilots,
+    /// Every supervisor, `*S`
+    Supervisors,
+    /// The flight plan handler, `FP`. See [`FLIGHT_PLAN_HANDLER_RECIPIENT`].
+    FlightPlanHandler,
+    /// The pseudo-frequency that ATC clients use to exchange information about aircraft, `@94835`. See [`AIRCRAFT_HANDLER_RECIPIENT`].
+    AircraftHandler,
+    /// Every client tuned to any of these frequencies, e.g. `@22800` or `@22800&@21800`
+    Frequencies(Vec<RadioFrequency>),
+    /// A single client
+    Callsign(Callsign),
+}
+
+impl Recipient {
+    /// Returns true if the message goes to more than one client
+    pub fn is_broadcast(&self) -> bool {
+        matches!(
+            self,
+            Recipient::Broadcast
+                | Recipient::AllAtc
+                | Recipient::AllPilots
+                | Recipient::Supervisors
+                | Recipient::Frequencies(_)
+                | Recipient::AircraftHandler
+        )
+    }
+
+    /// Classifies a special recipient, returning `None` for anything else
+    fn special(input: &str) -> Option<Recipient> {
+        match input {
+            SERVER_CALLSIGN => Some(Recipient::Server),
+            "*" => Some(Recipient::Broadcast),
+            "*A" => Some(Recipient::AllAtc),
+            "*P" => Some(Recipient::AllPilots),
+            "*S" => Some(Recipient::Supervisors),
+            FLIGHT_PLAN_HANDLER_RECIPIENT => Some(Recipient::FlightPlanHandler),
+            AIRCRAFT_HANDLER_RECIPIENT => Some(Recipient::AircraftHandler),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a recipient, rejecting empty callsigns, unknown broadcast addresses and invalid frequencies
+impl FromStr for Recipient {
+    type Err = FsdMessageParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.to_uppercase();
+        if let Some(recipient) = Recipient::special(&input) {
+            return Ok(recipient);
+        }
+        let invalid = || FsdMessageParseError::InvalidRecipient(s.to_string());
+        if input.starts_with('@') {
+            let frequencies = input
+                .split('&')
+                .map(|frequency| {
+                    RadioFrequency::from_str(frequency.strip_prefix('@').unwrap_or(frequency))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            return Ok(Recipient::Frequencies(frequencies));
+        }
+        Callsign::new(&input)
+            .map(Recipient::Callsign)
+            .map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<&str> for Recipient {
+    type Error = FsdMessageParseError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Callsign> for Recipient {
+    fn from(callsign: Callsign) -> Self {
+        Recipient::Callsign(callsign)
+    }
+}
+
+impl From<RadioFrequency> for Recipient {
+    fn from(frequency: RadioFrequency) -> Self {
+        Recipient::Frequencies(vec![frequency])
+    }
+}
+
+impl Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recipient::Server => f.write_str(SERVER_CALLSIGN),
+            Recipient::Broadcast => f.write_str("*"),
+            Recipient::AllAtc => f.write_str("*A"),
+            Recipient::AllPilots => f.write_str("*P"),
+            Recipient::Supervisors => f.write_str("*S"),
+            Recipient::FlightPlanHandler => f.write_str(FLIGHT_PLAN_HANDLER_RECIPIENT),
+            Recipient::AircraftHandler => f.write_str(AIRCRAFT_HANDLER_RECIPIENT),
+            Recipient::Frequencies(frequencies) => {
+                crate::util::write_frequencies_with_symbol(f, frequencies)
+            }
+            Recipient::Callsign(callsign) => f.write_str(callsign.as_str()),
+        }
+    }
+}
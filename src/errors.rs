@@ -1,5 +1,21 @@
+use std::{fmt::Display, ops::Range};
+
 use thiserror::Error;
 
+/// An error produced while parsing an FSD message
+///
+/// Errors returned by the parse functions are usually wrapped in [`WithContext`][FsdMessageParseError::WithContext],
+/// which records where in the line they occurred. Match on [`inner`][FsdMessageParseError::inner] to find out what
+/// went wrong.
+///
+/// # Example
+/// ```
+/// use fsd_interface::errors::FsdMessageParseError;
+///
+/// let error = fsd_interface::parse_message("#APBAW123:SERVER:1234567").unwrap_err();
+/// assert!(matches!(error, FsdMessageParseError::WithContext { .. }));
+/// assert!(matches!(error.inner(), FsdMessageParseError::InvalidFieldCount(..)));
+/// ```
 #[derive(Error, Debug)]
 pub enum FsdMessageParseError {
     #[error("invalid field count. Expected {0}, found {1}.")]
@@ -72,10 +88,153 @@ pub enum FsdMessageParseError {
     InvalidIPAddress(String),
     #[error("{0} is not a valid port")]
     InvalidPort(String),
+    #[error("{0} is not a valid weather value")]
+    InvalidWeatherValue(String),
+    #[error("{0} is not a valid recipient")]
+    InvalidRecipient(String),
+    #[error("{0} is not a valid callsign")]
+    InvalidCallsign(String),
+    #[error("{0} is not a valid CID")]
+    InvalidCid(String),
+    #[error("message is not valid UTF-8")]
+    InvalidUtf8,
+    /// Another error, with details of where in the message it occurred
+    #[error("{error} ({context})")]
+    WithContext {
+        error: Box<FsdMessageParseError>,
+        context: Box<ParseErrorContext>,
+    },
+}
+
+impl FsdMessageParseError {
+    /// Returns details of where in the message the error occurred, if known
+    ///
+    /// # Example
+    /// ```
+    /// let error = fsd_interface::parse_message("@N:BAW123:1200:1:abc:-0.45:1000:0:0:0").unwrap_err();
+    /// let context = error.context().unwrap();
+    /// assert_eq!(Some("@"), context.message_kind);
+    /// assert_eq!(Some(4), context.field_index);
+    /// assert_eq!(Some("latitude"), context.field_name);
+    /// assert_eq!(Some(17..20), context.span);
+    /// ```
+    pub fn context(&self) -> Option<&ParseErrorContext> {
+        match self {
+            FsdMessageParseError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error, without any [`ParseErrorContext`]
+    pub fn inner(&self) -> &FsdMessageParseError {
+        match self {
+            FsdMessageParseError::WithContext { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+
+    /// Attaches context pointing at field `index` of `fields`, unless the error already points at a field
+    pub(crate) fn at_field(self, fields: &[&str], index: usize, name: &'static str) -> Self {
+        if self.context().is_some_and(|c| c.field_index.is_some()) {
+            return self;
+        }
+        let context = ParseErrorContext::new(fields, Some((index, name)));
+        self.with_context(context)
+    }
+
+    /// Attaches context for the whole line made up of `fields`, if the error has no context yet
+    pub(crate) fn in_line(self, fields: &[&str]) -> Self {
+        if self.context().is_some() {
+            return self;
+        }
+        let context = ParseErrorContext::new(fields, None);
+        self.with_context(context)
+    }
+
+    /// Re-bases an error produced while parsing `fields[offset..]` so that its context refers to the whole of `fields`
+    pub(crate) fn offset_fields(self, fields: &[&str], offset: usize) -> Self {
+        match self {
+            FsdMessageParseError::WithContext { error, context } => {
+                let field = context
+                    .field_index
+                    .zip(context.field_name)
+                    .map(|(index, name)| (index + offset, name));
+                error.with_context(ParseErrorContext::new(fields, field))
+            }
+            error => error.in_line(fields),
+        }
+    }
+
+    fn with_context(self, context: ParseErrorContext) -> Self {
+        let error = match self {
+            FsdMessageParseError::WithContext { error, .. } => error,
+            error => Box::new(error),
+        };
+        FsdMessageParseError::WithContext {
+            error,
+            context: Box::new(context),
+        }
+    }
+}
+
+/// Describes where in a message a [`FsdMessageParseError`] occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrorContext {
+    /// The prefix identifying the message type, e.g. `#AP` or `@`, if it was recognised
+    pub message_kind: Option<&'static str>,
+    /// The zero-based index of the colon-delimited field that failed, where the prefix and first field are index 0
+    pub field_index: Option<usize>,
+    /// The name of the field that failed
+    pub field_name: Option<&'static str>,
+    /// The byte range of the failed field within [`raw_line`][Self::raw_line]
+    pub span: Option<Range<usize>>,
+    /// The line that was being parsed
+    pub raw_line: String,
+}
+
+impl ParseErrorContext {
+    pub(crate) fn new(fields: &[&str], field: Option<(usize, &'static str)>) -> Self {
+        let span = field.and_then(|(index, _)| {
+            let value = fields.get(index)?;
+            let start = fields[..index].iter().map(|f| f.len() + 1).sum::<usize>();
+            Some(start..start + value.len())
+        });
+        ParseErrorContext {
+            message_kind: fields
+                .first()
+                .and_then(|first| crate::util::message_prefix(first)),
+            field_index: field.map(|(index, _)| index),
+            field_name: field.map(|(_, name)| name),
+            span,
+            raw_line: fields.join(":"),
+        }
+    }
+}
+
+impl Display for ParseErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(kind) = self.message_kind {
+            write!(f, "in {kind} message")?;
+        } else {
+            write!(f, "in message")?;
+        }
+        if let (Some(index), Some(name)) = (self.field_index, self.field_name) {
+            write!(f, ", field {index} ({name})")?;
+        }
+        if let Some(span) = &self.span {
+            write!(f, " at bytes {}..{}", span.start, span.end)?;
+        }
+        write!(f, ": \"{}\"", self.raw_line)
+    }
 }
 
 /// An error message received from the FSD server
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
 pub enum FsdError {
     /// Attempted to log in with a callsign that is already in use
     #[error("Callsign in use")]
@@ -160,3 +319,76 @@ impl FsdError {
         FATAL_ERRORS.contains(&self.error_number())
     }
 }
+
+/// An error produced while splitting a raw byte stream into FSD messages
+#[derive(Error, Debug)]
+pub enum FsdFramingError {
+    /// A line exceeded the configured maximum length and was discarded
+    #[error("line exceeded the maximum length of {0} bytes")]
+    LineTooLong(usize),
+    /// A line was not valid UTF-8 and was discarded
+    #[error("line is not valid UTF-8")]
+    InvalidUtf8(Vec<u8>),
+    /// A complete line was received but could not be parsed as an FSD message
+    #[error(transparent)]
+    Parse(#[from] FsdMessageParseError),
+}
+
+/// A field that can't be serialised as it is, because it contains a character that would break the line
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{field} contains {character:?}")]
+pub struct FieldViolation {
+    /// The name of the field
+    pub field: &'static str,
+    /// The character that isn't allowed in the field
+    pub character: char,
+}
+
+/// A message that can't be serialised without producing a malformed line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsdEncodeError {
+    /// Every field at fault, in the order they appear in the message
+    pub violations: Vec<FieldViolation>,
+}
+
+impl Display for FsdEncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "message can't be encoded: ")?;
+        let mut violations = self.violations.iter().peekable();
+        while let Some(violation) = violations.next() {
+            write!(f, "{violation}")?;
+            if violations.peek().is_some() {
+                write!(f, ", ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FsdEncodeError {}
+
+/// An error returned when sending through an [`FsdClient`][crate::client::FsdClient]
+#[cfg(feature = "tokio")]
+#[derive(Error, Debug)]
+pub enum FsdClientError {
+    /// The message contains a field that would break the line, and wasn't sent
+    #[error(transparent)]
+    Encode(#[from] FsdEncodeError),
+    /// The client's connection has closed for good, and no more messages can be sent
+    #[error("the client has closed")]
+    Closed,
+}
+
+/// An error returned by the [blocking client][crate::blocking::FsdClient]
+#[derive(Error, Debug)]
+pub enum FsdConnectionError {
+    /// Reading from or writing to the connection failed, or a read timed out
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A line couldn't be decoded, and was skipped
+    #[error(transparent)]
+    Framing(#[from] FsdFramingError),
+    /// The session has ended
+    #[error("the session has closed: {0:?}")]
+    Closed(crate::session::CloseReason),
+}
@@ -0,0 +1,211 @@
+//! Splits raw FSD byte streams into messages, and encodes messages back into lines
+//!
+//! FSD messages are sent over TCP as lines terminated by `\r\n`. Reads from a socket can return
+//! any number of bytes, so a read may contain several messages, or only part of one.
+//! [`FsdFramer`] buffers the incoming bytes and yields each complete line as a parsed
+//! [`FsdMessageType`].
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{FsdFramer, FsdMessageType};
+//!
+//! let mut framer = FsdFramer::new();
+//! framer.push(b"$PISERVER:BAW123:84");
+//! assert!(framer.next().is_none());
+//!
+//! framer.push(b"15\r\n#DABAW123\n");
+//! assert!(matches!(framer.next(), Some(Ok(FsdMessageType::PingMessage(_)))));
+//! assert!(matches!(framer.next(), Some(Ok(FsdMessageType::AtcDeregisterMessage(_)))));
+//! assert!(framer.next().is_none());
+//!
+//! let from = fsd_interface::Callsign::new("BAW123").unwrap();
+//! let pong = fsd_interface::messages::PongMessage::new(from, fsd_interface::Recipient::Server, "8415");
+//! let mut outgoing = Vec::new();
+//! fsd_interface::framing::encode_line(&pong, &mut outgoing);
+//! assert_eq!(b"$POBAW123:SERVER:8415\r\n", outgoing.as_slice());
+//! ```
+
+use std::fmt::Display;
+
+use crate::{FsdMessageType, errors::FsdFramingError};
+
+/// The terminator appended to every outgoing FSD message
+pub const LINE_TERMINATOR: &str = "\r\n";
+
+/// The default maximum length of a single line, excluding its terminator
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 4096;
+
+/// The result of looking for the next line in a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frame {
+    /// A complete line of `len` bytes, excluding the terminator. `consumed` includes the terminator.
+    Line { len: usize, consumed: usize },
+    /// A line exceeded the maximum length. `consumed` bytes should be dropped from the buffer.
+    TooLong { consumed: usize },
+    /// `consumed` bytes should be dropped from the buffer without producing anything
+    Skip { consumed: usize },
+}
+
+/// Finds line boundaries in a buffer of raw bytes.
+///
+/// Shared by [`FsdFramer`] and the tokio codec so that both handle terminators and oversized
+/// lines in the same way.
+#[derive(Debug, Clone)]
+pub(crate) struct LineSplitter {
+    max_line_length: usize,
+    discarding: bool,
+}
+
+impl LineSplitter {
+    pub(crate) fn new(max_line_length: usize) -> Self {
+        LineSplitter {
+            max_line_length,
+            discarding: false,
+        }
+    }
+
+    pub(crate) fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Looks for the next frame at the start of `buf`. Returns `None` if more data is needed.
+    pub(crate) fn next_frame(&mut self, buf: &[u8]) -> Option<Frame> {
+        let newline = buf.iter().position(|b| *b == b'\n');
+        if self.discarding {
+            // Skip the remainder of an oversized line, which has already been reported
+            return match newline {
+                Some(i) => {
+                    self.discarding = false;
+                    Some(Frame::Skip { consumed: i + 1 })
+                }
+                None if buf.is_empty() => None,
+                None => Some(Frame::Skip {
+                    consumed: buf.len(),
+                }),
+            };
+        }
+
+        match newline {
+            Some(i) => {
+                let len = if i > 0 && buf[i - 1] == b'\r' {
+                    i - 1
+                } else {
+                    i
+                };
+                if len == 0 {
+                    Some(Frame::Skip { consumed: i + 1 })
+                } else if len > self.max_line_length {
+                    Some(Frame::TooLong { consumed: i + 1 })
+                } else {
+                    Some(Frame::Line {
+                        len,
+                        consumed: i + 1,
+                    })
+                }
+            }
+            // Allow one extra byte for a '\r' that has arrived without its '\n'
+            None if buf.len() > self.max_line_length + 1 => {
+                self.discarding = true;
+                Some(Frame::TooLong {
+                    consumed: buf.len(),
+                })
+            }
+            None => None,
+        }
+    }
+}
+
+/// Converts a complete line into a parsed message
+pub(crate) fn decode_line(line: &[u8]) -> Result<FsdMessageType, FsdFramingError> {
+    let line =
+        std::str::from_utf8(line).map_err(|_| FsdFramingError::InvalidUtf8(line.to_vec()))?;
+    Ok(crate::parse_message(line)?)
+}
+
+/// Buffers a raw FSD byte stream and yields complete, parsed messages.
+///
+/// Bytes are added with [`FsdFramer::push`], and messages are retrieved by iterating over the framer.
+/// Lines may be terminated by `\r\n` or a bare `\n`, and empty lines are ignored. A line that
+/// is too long, is not valid UTF-8 or fails to parse is reported as an error, and the framer carries
+/// on from the next line.
+#[derive(Debug, Clone)]
+pub struct FsdFramer {
+    buffer: Vec<u8>,
+    /// Where the unread bytes in `buffer` start. Read bytes are only dropped on the next push, so that reading
+    /// many lines from one push doesn't move the rest of the buffer each time.
+    start: usize,
+    splitter: LineSplitter,
+}
+
+impl Default for FsdFramer {
+    fn default() -> Self {
+        FsdFramer::new()
+    }
+}
+
+impl FsdFramer {
+    /// Creates a new [`FsdFramer`] with a maximum line length of [`DEFAULT_MAX_LINE_LENGTH`]
+    pub fn new() -> Self {
+        FsdFramer::with_max_line_length(DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a new [`FsdFramer`] which rejects lines longer than `max_line_length` bytes, excluding the terminator
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        FsdFramer {
+            buffer: Vec::new(),
+            start: 0,
+            splitter: LineSplitter::new(max_line_length),
+        }
+    }
+
+    pub fn max_line_length(&self) -> usize {
+        self.splitter.max_line_length()
+    }
+
+    /// Adds a chunk of bytes received from the stream
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.drain(..self.start);
+        self.start = 0;
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The number of buffered bytes that have not yet formed a complete line
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+}
+
+impl Iterator for FsdFramer {
+    type Item = Result<FsdMessageType, FsdFramingError>;
+
+    /// Returns the next complete message, or `None` if more bytes are needed
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let unread = &self.buffer[self.start..];
+            match self.splitter.next_frame(unread)? {
+                Frame::Line { len, consumed } => {
+                    let result = decode_line(&unread[..len]);
+                    self.start += consumed;
+                    return Some(result);
+                }
+                Frame::TooLong { consumed } => {
+                    self.start += consumed;
+                    return Some(Err(FsdFramingError::LineTooLong(self.max_line_length())));
+                }
+                Frame::Skip { consumed } => {
+                    self.start += consumed;
+                }
+            }
+        }
+    }
+}
+
+/// Appends a message and its `\r\n` terminator to `dst`.
+///
+/// The message is written as it is. Use [`FsdMessage::try_encode`][crate::FsdMessage::try_encode] first if it
+/// may contain text that would break the line.
+pub fn encode_line(message: &impl Display, dst: &mut Vec<u8>) {
+    use std::io::Write;
+    // Writing to a Vec cannot fail
+    let _ = write!(dst, "{message}{LINE_TERMINATOR}");
+}
@@ -0,0 +1,306 @@
+//! Callsigns and CIDs, validated when they are created so that they can always be sent back over the wire
+
+use std::{
+    borrow::Borrow,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str::FromStr,
+};
+
+use crate::errors::FsdMessageParseError;
+
+/// The most bytes a [`Callsign`] or [`Cid`] can hold. This keeps them the same size as a `String`, without the allocation.
+const CAPACITY: usize = 23;
+
+/// A short string stored inline, holding only printable ASCII characters other than `:`
+#[derive(Clone, Copy)]
+struct InlineStr {
+    len: u8,
+    bytes: [u8; CAPACITY],
+}
+
+impl InlineStr {
+    /// Stores `value`, optionally uppercased. Returns `None` if it is too long, or contains a colon or a
+    /// character that would break the line when serialised.
+    const fn new(value: &str, uppercase: bool) -> Option<InlineStr> {
+        let value = value.as_bytes();
+        if value.len() > CAPACITY {
+            return None;
+        }
+        let mut bytes = [0; CAPACITY];
+        let mut i = 0;
+        while i < value.len() {
+            let b = value[i];
+            if (!b.is_ascii_graphic() && b != b' ') || b == b':' {
+                return None;
+            }
+            bytes[i] = if uppercase { b.to_ascii_uppercase() } else { b };
+            i += 1;
+        }
+        Some(InlineStr {
+            len: value.len() as u8,
+            bytes,
+        })
+    }
+
+    fn as_str(&self) -> &str {
+        // Only ASCII is ever stored, so this can't fail
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+/// The callsign of a client or server, such as `BAW123` or `EGLL_TWR`.
+///
+/// A callsign is between 1 and [`Callsign::MAX_LEN`] characters long, and made up of ASCII letters, digits,
+/// `_` and `-`. Letters are uppercased when it is created.
+///
+/// Callsigns are stored inline, so they are [`Copy`] and make cheap hash map keys. A map keyed by callsign
+/// can be looked up with a `&str`.
+///
+/// Callsigns parsed in [`ParseMode::Lenient`][crate::ParseMode::Lenient] are kept even if they break these
+/// rules, with a [`ParseWarning::InvalidIdentifier`][crate::ParseWarning::InvalidIdentifier], as long as they
+/// fit and contain no colons or control characters.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use fsd_interface::Callsign;
+///
+/// let callsign: Callsign = "egll_twr".parse().unwrap();
+/// assert_eq!("EGLL_TWR", callsign);
+///
+/// let mut frequencies = HashMap::new();
+/// frequencies.insert(callsign, "118.500");
+/// assert_eq!(Some(&"118.500"), frequencies.get("EGLL_TWR"));
+///
+/// assert!("BAW123:EXTRA".parse::<Callsign>().is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Callsign(InlineStr);
+
+impl Callsign {
+    /// The longest a callsign can be
+    pub const MAX_LEN: usize = CAPACITY;
+
+    /// Validates and uppercases `callsign`
+    pub fn new(callsign: impl AsRef<str>) -> Result<Callsign, FsdMessageParseError> {
+        let value = callsign.as_ref();
+        let valid = !value.is_empty()
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+        match InlineStr::new(value, true) {
+            Some(callsign) if valid => Ok(Callsign(callsign)),
+            _ => Err(FsdMessageParseError::InvalidCallsign(value.to_string())),
+        }
+    }
+
+    /// Creates a callsign from a string literal, for use in constants.
+    ///
+    /// # Panics
+    /// If `callsign` is not a valid callsign. In a constant, this is a compile error.
+    pub const fn from_static(callsign: &'static str) -> Callsign {
+        let bytes = callsign.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            assert!(
+                b.is_ascii_alphanumeric() || b == b'_' || b == b'-',
+                "invalid character in callsign"
+            );
+            i += 1;
+        }
+        match InlineStr::new(callsign, true) {
+            Some(callsign) if !bytes.is_empty() => Callsign(callsign),
+            _ => panic!("callsign is empty or too long"),
+        }
+    }
+
+    /// Keeps a callsign that breaks the naming rules, as long as it can still be stored and sent
+    pub(crate) fn lenient(callsign: &str) -> Option<Callsign> {
+        InlineStr::new(callsign, true).map(Callsign)
+    }
+}
+
+/// A client's certificate ID: a VATSIM CID, an IVAO VID, or a user ID on a private server.
+///
+/// A CID is between 1 and [`Cid::MAX_LEN`] ASCII letters and digits. Like [`Callsign`], it is stored inline,
+/// and CIDs parsed leniently are kept even if they break these rules.
+///
+/// # Example
+/// ```
+/// use fsd_interface::Cid;
+///
+/// let cid: Cid = "1234567".parse().unwrap();
+/// assert_eq!("1234567", cid.as_str());
+/// assert!("".parse::<Cid>().is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Cid(InlineStr);
+
+impl Cid {
+    /// The longest a CID can be
+    pub const MAX_LEN: usize = CAPACITY;
+
+    /// Validates `cid`
+    pub fn new(cid: impl AsRef<str>) -> Result<Cid, FsdMessageParseError> {
+        let value = cid.as_ref();
+        let valid = !value.is_empty() && value.bytes().all(|b| b.is_ascii_alphanumeric());
+        match InlineStr::new(value, false) {
+            Some(cid) if valid => Ok(Cid(cid)),
+            _ => Err(FsdMessageParseError::InvalidCid(value.to_string())),
+        }
+    }
+
+    /// Keeps a CID that breaks the rules, as long as it can still be stored and sent
+    pub(crate) fn lenient(cid: &str) -> Option<Cid> {
+        InlineStr::new(cid, false).map(Cid)
+    }
+}
+
+/// An identifier that is validated on creation, but can be kept when parsing leniently if it breaks the rules
+pub(crate) trait Identifier: FromStr<Err = FsdMessageParseError> {
+    fn lenient(value: &str) -> Option<Self>;
+}
+
+/// Implements the string-like traits shared by [`Callsign`] and [`Cid`]
+macro_rules! impl_identifier {
+    ($identifier: ident) => {
+        impl $identifier {
+            pub fn as_str(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl Identifier for $identifier {
+            fn lenient(value: &str) -> Option<Self> {
+                $identifier::lenient(value)
+            }
+        }
+
+        impl FromStr for $identifier {
+            type Err = FsdMessageParseError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $identifier::new(s)
+            }
+        }
+
+        impl TryFrom<&str> for $identifier {
+            type Error = FsdMessageParseError;
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                $identifier::new(value)
+            }
+        }
+
+        impl TryFrom<String> for $identifier {
+            type Error = FsdMessageParseError;
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                $identifier::new(value)
+            }
+        }
+
+        impl Deref for $identifier {
+            type Target = str;
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl AsRef<str> for $identifier {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl Borrow<str> for $identifier {
+            fn borrow(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl Display for $identifier {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Debug for $identifier {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_tuple(stringify!($identifier))
+                    .field(&self.as_str())
+                    .finish()
+            }
+        }
+
+        // Compared and hashed as strings, so that `Borrow<str>` can be used for map lookups
+        impl PartialEq for $identifier {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+        impl Eq for $identifier {}
+
+        impl PartialOrd for $identifier {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for $identifier {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.as_str().cmp(other.as_str())
+            }
+        }
+
+        impl Hash for $identifier {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.as_str().hash(state)
+            }
+        }
+
+        impl PartialEq<str> for $identifier {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+        impl PartialEq<&str> for $identifier {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+        impl PartialEq<$identifier> for str {
+            fn eq(&self, other: &$identifier) -> bool {
+                self == other.as_str()
+            }
+        }
+        impl PartialEq<$identifier> for &str {
+            fn eq(&self, other: &$identifier) -> bool {
+                *self == other.as_str()
+            }
+        }
+
+        /// Serialised as a string. Deserialising accepts anything a lenient parse would, so that parsed
+        /// messages can always be read back.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $identifier {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $identifier {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                $identifier::lenient(&value).ok_or_else(|| {
+                    serde::de::Error::custom(format!(
+                        "{value} is not a valid {}",
+                        stringify!($identifier)
+                    ))
+                })
+            }
+        }
+    };
+}
+
+impl_identifier!(Callsign);
+impl_identifier!(Cid);
@@ -37,11 +37,20 @@
 //!
 //! ## What does this crate do?
 //!
-//! At the moment, this crate only works with the VATSIM flavour of the FSD protocol. In due course, it will support legacy FSD packets as well as IVAO packets.
+//! The crate supports the VATSIM, classic (legacy) and IVAO flavours of the FSD protocol. Classic support covers the legacy weather packets, the missing `$DI`/`$ID` handshake and the legacy protocol revision; its `#AP` and `#AA` registrations are read and written with the same fields as VATSIM's, and the layouts of older servers that differ from them aren't supported. The dialect is chosen when parsing with `ParseOptions`, and `Dialect::encode` serialises messages for a given dialect. When a peer's dialect isn't known in advance, `DialectDetector` works it out from the first lines of the connection.
 //!
 //! - Identifies if a string of text is a valid FSD protocol message and identifies the type
 //! - Deserialises it into a struct so that you can work with the information in it
-//! - Serialises structs into valid, validity-checked FSD message strings
+//! - Serialises structs into valid, validity-checked FSD message strings, with `try_encode` rejecting any text that would break the line
+//! - Encodes messages straight into a caller's buffer with `encode_into`, without allocating, for servers sending one message to many clients
+//! - Reads and writes lines in Windows-1252 or Latin-1 with `parse_message_bytes` and `encode_bytes`, for older clients that don't use UTF-8
+//! - Compares messages with `==`, or with `ApproxEq` to the precision positions are sent with
+//! - Exposes the sender, recipient and type of every message through the `FsdMessage` trait, for generic logging, routing and filtering
+//! - Drives a client's login, pings and disconnection with `ClientSession`, a state machine that doesn't do any I/O itself
+//! - Connects to a server without an async runtime with `blocking::FsdClient`, for small scripts and bots
+//! - Runs a private FSD network with the `server` module, or the `fsd-server` binary with the `tokio` feature
+//! - Works out who should receive each message from clients' positions and visibility ranges with `Router`
+//! - Validates callsigns and CIDs on creation with the `Callsign` and `Cid` types, which are cheap to copy and to use as map keys
 //!
 //! ## Examples
 //! ```
@@ -54,18 +63,36 @@
 //!
 //!     // And access its data
 //!     assert_eq!("EHAM_GND", client_query_message.from.as_str());
-//!     assert_eq!("@94835", client_query_message.to.as_str());
+//!     assert_eq!(fsd_interface::Recipient::AircraftHandler, client_query_message.to);
 //!     if let fsd_interface::ClientQueryType::WhoHas { aircraft_callsign } = client_query_message.query_type {
 //!         assert_eq!("KLM167", aircraft_callsign.as_str());
 //!     }
 //!
 //!     // Plus, on the flip side, we can create our own messages and serialise them
-//!     let new_message = fsd_interface::messages::ClientQueryMessage::who_has("LIRF_TWR", "@94835", "ITY1561");
+//!     let from = fsd_interface::Callsign::new("LIRF_TWR").unwrap();
+//!     let aircraft = fsd_interface::Callsign::new("ITY1561").unwrap();
+//!     let new_message = fsd_interface::messages::ClientQueryMessage::who_has(from, fsd_interface::Recipient::AircraftHandler, aircraft);
 //!     assert_eq!(String::from("$CQLIRF_TWR:@94835:WH:ITY1561"), new_message.to_string());
 //! }
 //! ```
 //!
 //!
+//! ## Optional features
+//!
+//! - `tokio` - enables `FsdCodec`, a tokio-util codec for FSD streams, and `FsdClient`, an async client that handles the login, pings and reconnection
+//! - `serde` - implements serde's `Serialize` and `Deserialize` for all messages and the types they contain. Enums that carry
+//!   data are adjacently tagged, with the variant name in `type` and its contents in `data`.
+//!
+//! ```
+//! # #[cfg(feature = "serde")]
+//! # {
+//! let message = fsd_interface::parse_message("$CQEHAM_GND:@94835:WH:KLM167").unwrap();
+//! let json = serde_json::to_value(&message).unwrap();
+//! assert_eq!("ClientQueryMessage", json["type"]);
+//! assert_eq!("WhoHas", json["data"]["query_type"]["type"]);
+//! # }
+//! ```
+//!
 //! ## Disclaimer
 //!
 //! It is against the VATSIM [Code of Conduct](https://vatsim.net/docs/policy/code-of-conduct) and
@@ -80,26 +107,96 @@
 #![allow(clippy::too_many_arguments)]
 
 mod aircraft_config;
+mod approx;
+pub mod blocking;
+pub mod borrowed;
+pub mod charset;
+#[cfg(feature = "tokio")]
+pub mod client;
+#[cfg(feature = "tokio")]
+pub mod codec;
+pub mod dialect;
 mod enums;
 
 /// Contains error types used in the crate
 pub mod errors;
 
+pub mod framing;
+mod identifiers;
 pub mod messages;
+pub mod options;
+mod prefix;
+pub mod router;
+pub mod server;
+pub mod session;
 mod structs;
 mod util;
+mod validation;
 
 pub use aircraft_config::*;
-pub use chrono::{DateTime, Utc};
+pub use approx::ApproxEq;
+pub use borrowed::FsdMessageRef;
+pub use charset::Charset;
+#[cfg(feature = "tokio")]
+pub use client::FsdClient;
+pub use chrono::{DateTime, NaiveTime, Utc};
+#[cfg(feature = "tokio")]
+pub use codec::FsdCodec;
+pub use dialect::Dialect;
 pub use enums::*;
+pub use framing::FsdFramer;
+pub use identifiers::{Callsign, Cid};
+pub use messages::FsdMessage;
+pub use options::{ParseMode, ParseOptions, ParseWarning, ParsedMessage};
+pub use router::Router;
+pub use session::ClientSession;
 pub use structs::*;
 
 /// Deserialises a valid FSD message string into a struct.
 ///
 /// If the string is a valid FSD message, deserialises it into the appropriate struct and returns it inside an [`FsdMessageType`] enum variant that indicates which type it is.
 /// If there are any validation errors, an [`FsdMessageParseError`][errors::FsdMessageParseError] is returned instead.
+///
+/// Parsing never panics, whatever the input, so it is safe to call on lines received from untrusted clients.
+///
+/// Deviations from the protocol are accepted where the message can still be understood, except for extra fields on messages of a fixed length such as flight plans, which are rejected. Use [`parse_message_with`] to parse strictly, or to find out what was wrong with a message that was accepted.
 pub fn parse_message(
     message: impl AsRef<str>,
 ) -> Result<FsdMessageType, errors::FsdMessageParseError> {
-    FsdMessageType::identify(message.as_ref())
+    FsdMessageType::identify(message.as_ref(), &mut options::ParseContext::default())
+}
+
+/// Deserialises an FSD message string into a struct, using the given [`ParseOptions`].
+///
+/// In [`ParseMode::Strict`], any deviation from the protocol is returned as an error. In [`ParseMode::Lenient`],
+/// deviations are accepted where possible and returned as [`ParseWarning`]s alongside the message.
+pub fn parse_message_with(
+    message: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<ParsedMessage, errors::FsdMessageParseError> {
+    let mut cx = options::ParseContext::new(options);
+    let message = FsdMessageType::identify(message.as_ref(), &mut cx)?;
+    Ok(ParsedMessage {
+        message,
+        warnings: cx.into_warnings(),
+    })
+}
+
+/// Deserialises an FSD message received as raw bytes in the given [`Charset`].
+///
+/// Use this for clients that send text in Windows-1252 or Latin-1 rather than UTF-8. Deviations from the protocol are
+/// treated as in [`parse_message`].
+pub fn parse_message_bytes(
+    message: &[u8],
+    charset: Charset,
+) -> Result<FsdMessageType, errors::FsdMessageParseError> {
+    FsdMessageType::identify(&charset.decode(message)?, &mut options::ParseContext::default())
+}
+
+/// Deserialises an FSD message string into a borrowed [`FsdMessageRef`] without allocating.
+///
+/// Position updates are parsed straight from the input. Other message types are identified but left unparsed
+/// until [`FsdMessageRef::to_owned`] is called, so a line that is later discarded costs almost nothing.
+pub fn parse_message_ref(message: &str) -> Result<FsdMessageRef<'_>, errors::FsdMessageParseError> {
+    FsdMessageRef::identify(message)
 }
@@ -2,20 +2,34 @@
 //!
 //!
 
-use std::{fmt::Display, net::Ipv4Addr};
+use std::{borrow::Cow, fmt::Display, net::Ipv4Addr};
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, NaiveTime};
 
 use crate::{
     aircraft_config::AircraftConfig,
+    borrowed::{
+        AtcPositionUpdateRef, PilotPositionUpdateRef, VelocityPositionRef,
+        VelocityPositionStoppedRef,
+    },
     enums::{
         AtcRating, AtcType, AtisLine, ClientCapability, ClientQueryType, ClientResponseType,
-        PilotRating, ProtocolRevision, SharedStateType, SimulatorType, TransponderMode,
-        VoiceCapability,
+        MessageKind, PilotRating, ProtocolRevision, Recipient, SharedStateType, SimulatorType,
+        TransponderMode, VoiceCapability,
+    },
+    errors::{FieldViolation, FsdEncodeError, FsdError, FsdMessageParseError},
+    identifiers::{Callsign, Cid},
+    options::{ParseContext, ParseFields, ParseWarning},
+    structs::{
+        CloudLayer, FlightPlan, PlaneInfo, RadioFrequency, TemperatureLayer, TransponderCode,
+        WindLayer,
+    },
+    util::{
+        self, check_exact_num_fields, check_min_num_fields, check_num_fields, field,
+        impl_try_from_fields, parse_field, parse_field_or, parse_identifier, parse_recipient,
+        parse_sender,
     },
-    errors::{FsdError, FsdMessageParseError},
-    structs::{FlightPlan, PlaneInfo, RadioFrequency, TransponderCode},
-    util, LandLineCommand, LandLineType, Level, ScratchPad,
+    validation, Charset, Dialect, LandLineCommand, LandLineType, Level, ScratchPad,
 };
 
 pub const SERVER_CALLSIGN: &str = "SERVER";
@@ -23,29 +37,377 @@ pub const ATC_TEXT_CHANNEL_FREQUENCY: RadioFrequency = RadioFrequency(149, 999);
 pub const AIRCRAFT_HANDLER_RECIPIENT: &str = "@94835";
 pub const FLIGHT_PLAN_HANDLER_RECIPIENT: &str = "FP";
 
-macro_rules! check_min_num_fields {
-    ($fields: ident, $i: literal) => {
-        if $fields.len() < $i {
-            return Err(FsdMessageParseError::InvalidFieldCount($i, $fields.len()));
+impl_try_from_fields!(
+    AtcRegisterMessage,
+    PilotRegisterMessage,
+    AtcDeregisterMessage,
+    PilotDeregisterMessage,
+    AtcPositionUpdateMessage,
+    AtcSecondaryVisCentreMessage,
+    PilotPositionUpdateMessage,
+    AuthenticationChallengeMessage,
+    AuthenticationResponseMessage,
+    TextMessage,
+    FrequencyMessage,
+    ChangeServerMessage,
+    InitialServerHandshakeMessage,
+    InitialClientHandshakeMessage,
+    SendFastPositionUpdatesMessage,
+    VelocityPositionStoppedMessage,
+    VelocityPositionSlowMessage,
+    VelocityPositionFastMessage,
+    KillMessage,
+    MetarRequestMessage,
+    MetarResponseMessage,
+    IvaoAtcRegisterMessage,
+    IvaoPilotRegisterMessage,
+    IvaoClientQueryMessage,
+    WeatherRequestMessage,
+    TemperatureDataMessage,
+    WindDataMessage,
+    CloudDataMessage,
+    WindDeltaMessage,
+    PingMessage,
+    PongMessage,
+    PlaneInfoRequestMessage,
+    PlaneInfoResponseMessage,
+    FSInnPlaneInformationRequestMessage,
+    FSInnPlaneInformationResponseMessage,
+    FsdErrorMessage,
+    FlightPlanMessage,
+    FlightPlanAmendmentMessage,
+    ClientQueryMessage,
+    ClientQueryResponseMessage,
+    HandoffOfferMessage,
+    SharedStateMessage,
+    HandoffAcceptMessage,
+);
+
+/// Functionality shared by every FSD message, so that messages can be logged, routed and filtered without
+/// matching on each type.
+///
+/// Implemented by every struct in this module, and by [`FsdMessageType`][crate::FsdMessageType], which delegates to the message it holds.
+///
+/// # Example
+/// ```
+/// use fsd_interface::{Callsign, FsdMessage, MessageKind, Recipient};
+///
+/// let message = fsd_interface::parse_message("#TMEGLL_TWR:BAW123:Contact Heathrow ground").unwrap();
+/// assert_eq!("EGLL_TWR", message.sender());
+/// assert_eq!(
+///     Some(&Recipient::Callsign(Callsign::new("BAW123").unwrap())),
+///     message.recipient().as_deref()
+/// );
+/// assert_eq!("#TM", message.prefix());
+/// assert_eq!(MessageKind::Text, message.kind());
+/// assert_eq!("#TMEGLL_TWR:BAW123:Contact Heathrow ground", message.encode());
+/// ```
+pub trait FsdMessage: Display {
+    /// The callsign of the client or server that sent the message
+    fn sender(&self) -> &str;
+
+    /// Who the message is addressed to, if it is addressed at all. Position updates aren't addressed, and go to
+    /// every client in range.
+    fn recipient(&self) -> Option<Cow<'_, Recipient>>;
+
+    /// The prefix identifying the message type on the wire, e.g. `#TM`
+    fn prefix(&self) -> &str;
+
+    /// The type of message
+    fn kind(&self) -> MessageKind;
+
+    /// Every field that can't be serialised as it is, because it contains a colon or a line break that would
+    /// corrupt the line. Empty if the message can be sent.
+    fn violations(&self) -> Vec<FieldViolation>;
+
+    /// Serialises the message, without the line terminator
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serialises the message into `dst`, without the line terminator. Nothing is allocated along the way, so a
+    /// message sent to many clients can be encoded once into a reused buffer.
+    ///
+    /// Like [`encode`][FsdMessage::encode], this doesn't check the message. Call
+    /// [`violations`][FsdMessage::violations] first if it may contain text that would break the line.
+    ///
+    /// # Example
+    /// ```
+    /// use fsd_interface::{FsdMessage, parse_message};
+    ///
+    /// let update = parse_message("%EGLL_TWR:18500:4:50:5:51.47750:-0.46139:0").unwrap();
+    ///
+    /// let mut buffer = Vec::with_capacity(512);
+    /// update.encode_into(&mut buffer).unwrap();
+    /// buffer.extend_from_slice(b"\r\n");
+    /// assert_eq!(&b"%EGLL_TWR:18500:4:50:5:51.47750:-0.46139:0\r\n"[..], &buffer[..]);
+    ///
+    /// // The same buffer can be cleared and reused for the next message
+    /// buffer.clear();
+    /// ```
+    fn encode_into(&self, dst: &mut dyn std::io::Write) -> std::io::Result<()> {
+        write!(dst, "{self}")
+    }
+
+    /// Serialises the message in the given [`Charset`], without the line terminator, for peers that don't use
+    /// UTF-8. Characters in free text that the charset can't represent are replaced with `?`.
+    fn encode_bytes(&self, charset: Charset) -> Vec<u8> {
+        charset.encode(&self.encode()).into_owned()
+    }
+
+    /// Serialises the message, without the line terminator, after checking that it will be read back as the
+    /// same message. Returns every field that would break the line, rather than only the first.
+    ///
+    /// # Example
+    /// ```
+    /// use fsd_interface::{Callsign, FsdMessage, errors::FieldViolation, messages::TextMessage};
+    ///
+    /// let from = Callsign::new("EGLL_TWR").unwrap();
+    /// let to = Callsign::new("BAW123").unwrap();
+    ///
+    /// // The text of a message is its last field, so it can contain colons
+    /// let message = TextMessage::new(from, to, "Runway 27L: cleared to land");
+    /// assert_eq!("#TMEGLL_TWR:BAW123:Runway 27L: cleared to land", message.try_encode().unwrap());
+    ///
+    /// let message = TextMessage::new(from, to, "Cleared to land\r\n#DPEGLL_TWR");
+    /// let error = message.try_encode().unwrap_err();
+    /// assert_eq!(
+    ///     vec![
+    ///         FieldViolation { field: "message", character: '\r' },
+    ///         FieldViolation { field: "message", character: '\n' },
+    ///     ],
+    ///     error.violations
+    /// );
+    /// ```
+    fn try_encode(&self) -> Result<String, FsdEncodeError> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(self.encode())
+        } else {
+            Err(FsdEncodeError { violations })
         }
-    };
+    }
 }
 
-macro_rules! check_exact_num_fields {
-    ($fields: ident, $i: literal) => {
-        if $fields.len() != $i {
-            return Err(FsdMessageParseError::InvalidFieldCount($i, $fields.len()));
+/// Implements [`FsdMessage`] for a message with the given kind, prefix, and sender and recipient fields
+macro_rules! impl_fsd_message {
+    ($message: ty, $kind: ident, $prefix: literal, $sender: ident) => {
+        impl FsdMessage for $message {
+            fn sender(&self) -> &str {
+                &self.$sender
+            }
+            fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+                None
+            }
+            fn prefix(&self) -> &str {
+                $prefix
+            }
+            fn kind(&self) -> MessageKind {
+                MessageKind::$kind
+            }
+            fn violations(&self) -> Vec<FieldViolation> {
+                validation::violations(self)
+            }
+        }
+    };
+    ($message: ty, $kind: ident, $prefix: literal, $sender: ident, $recipient: ident) => {
+        impl FsdMessage for $message {
+            fn sender(&self) -> &str {
+                &self.$sender
+            }
+            fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+                Some(Cow::Borrowed(&self.$recipient))
+            }
+            fn prefix(&self) -> &str {
+                $prefix
+            }
+            fn kind(&self) -> MessageKind {
+                MessageKind::$kind
+            }
+            fn violations(&self) -> Vec<FieldViolation> {
+                validation::violations(self)
+            }
         }
     };
 }
 
+impl_fsd_message!(AtcRegisterMessage, AtcRegister, "#AA", from, to);
+impl_fsd_message!(PilotRegisterMessage, PilotRegister, "#AP", from, to);
+impl_fsd_message!(AtcDeregisterMessage, AtcDeregister, "#DA", from);
+impl_fsd_message!(PilotDeregisterMessage, PilotDeregister, "#DP", from);
+impl_fsd_message!(AtcPositionUpdateMessage, AtcPositionUpdate, "%", callsign);
+impl_fsd_message!(
+    AtcSecondaryVisCentreMessage,
+    AtcSecondaryVisCentre,
+    "'",
+    callsign
+);
+impl_fsd_message!(
+    PilotPositionUpdateMessage,
+    PilotPositionUpdate,
+    "@",
+    callsign
+);
+impl_fsd_message!(
+    AuthenticationChallengeMessage,
+    AuthenticationChallenge,
+    "$ZC",
+    from,
+    to
+);
+impl_fsd_message!(
+    AuthenticationResponseMessage,
+    AuthenticationResponse,
+    "$ZR",
+    from,
+    to
+);
+impl_fsd_message!(TextMessage, Text, "#TM", from, to);
+impl_fsd_message!(ChangeServerMessage, ChangeServer, "$XX", from, to);
+impl_fsd_message!(
+    InitialServerHandshakeMessage,
+    InitialServerHandshake,
+    "$DI",
+    from,
+    to
+);
+impl_fsd_message!(
+    InitialClientHandshakeMessage,
+    InitialClientHandshake,
+    "$ID",
+    from,
+    to
+);
+impl_fsd_message!(
+    SendFastPositionUpdatesMessage,
+    SendFastPositionUpdates,
+    "$SF",
+    from,
+    to
+);
+impl_fsd_message!(
+    VelocityPositionStoppedMessage,
+    VelocityPositionStopped,
+    "#ST",
+    from
+);
+impl_fsd_message!(
+    VelocityPositionSlowMessage,
+    VelocityPositionSlow,
+    "#SL",
+    from
+);
+impl_fsd_message!(VelocityPositionFastMessage, VelocityPositionFast, "^", from);
+impl_fsd_message!(KillMessage, Kill, "$!!", from, to);
+impl_fsd_message!(MetarRequestMessage, MetarRequest, "$AX", from, to);
+impl_fsd_message!(MetarResponseMessage, MetarResponse, "$AR", from, to);
+impl_fsd_message!(PingMessage, Ping, "$PI", from, to);
+impl_fsd_message!(PongMessage, Pong, "$PO", from, to);
+impl_fsd_message!(PlaneInfoRequestMessage, PlaneInfoRequest, "#SB", from, to);
+impl_fsd_message!(PlaneInfoResponseMessage, PlaneInfoResponse, "#SB", from, to);
+impl_fsd_message!(FsdErrorMessage, FsdError, "$ER", from, to);
+impl_fsd_message!(FlightPlanMessage, FlightPlan, "$FP", callsign, to);
+impl_fsd_message!(
+    FlightPlanAmendmentMessage,
+    FlightPlanAmendment,
+    "$AM",
+    from,
+    to
+);
+impl_fsd_message!(
+    FSInnPlaneInformationRequestMessage,
+    FSInnPlaneInformationRequest,
+    "#SB",
+    from,
+    to
+);
+impl_fsd_message!(
+    FSInnPlaneInformationResponseMessage,
+    FSInnPlaneInformationResponse,
+    "#SB",
+    from,
+    to
+);
+impl_fsd_message!(ClientQueryMessage, ClientQuery, "$CQ", from, to);
+impl_fsd_message!(
+    ClientQueryResponseMessage,
+    ClientQueryResponse,
+    "$CR",
+    from,
+    to
+);
+impl_fsd_message!(HandoffOfferMessage, HandoffOffer, "$HO", from, to);
+impl_fsd_message!(HandoffAcceptMessage, HandoffAccept, "$HA", from, to);
+impl_fsd_message!(SharedStateMessage, SharedState, "#PC", from, to);
+impl_fsd_message!(WeatherRequestMessage, WeatherRequest, "#RW", from, to);
+impl_fsd_message!(TemperatureDataMessage, TemperatureData, "#TD", from, to);
+impl_fsd_message!(WindDataMessage, WindData, "#WD", from, to);
+impl_fsd_message!(CloudDataMessage, CloudData, "#CD", from, to);
+impl_fsd_message!(WindDeltaMessage, WindDelta, "#DL", from, to);
+impl_fsd_message!(IvaoClientQueryMessage, IvaoClientQuery, "$CQ", from, to);
+
+impl FsdMessage for FrequencyMessage {
+    fn sender(&self) -> &str {
+        &self.from
+    }
+    fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+        Some(Cow::Owned(Recipient::Frequencies(self.to.clone())))
+    }
+    fn prefix(&self) -> &str {
+        "#TM"
+    }
+    fn kind(&self) -> MessageKind {
+        MessageKind::Frequency
+    }
+    fn violations(&self) -> Vec<FieldViolation> {
+        validation::violations(self)
+    }
+}
+
+impl FsdMessage for IvaoAtcRegisterMessage {
+    fn sender(&self) -> &str {
+        self.registration.sender()
+    }
+    fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+        self.registration.recipient()
+    }
+    fn prefix(&self) -> &str {
+        "#AA"
+    }
+    fn kind(&self) -> MessageKind {
+        MessageKind::IvaoAtcRegister
+    }
+    fn violations(&self) -> Vec<FieldViolation> {
+        validation::violations(self)
+    }
+}
+
+impl FsdMessage for IvaoPilotRegisterMessage {
+    fn sender(&self) -> &str {
+        self.registration.sender()
+    }
+    fn recipient(&self) -> Option<Cow<'_, Recipient>> {
+        self.registration.recipient()
+    }
+    fn prefix(&self) -> &str {
+        "#AP"
+    }
+    fn kind(&self) -> MessageKind {
+        MessageKind::IvaoPilotRegister
+    }
+    fn violations(&self) -> Vec<FieldViolation> {
+        validation::violations(self)
+    }
+}
+
 /// Sent by an ATC client to register itself on the network after the initial handshake
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtcRegisterMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub real_name: String,
-    pub cid: String,
+    pub cid: Cid,
     pub password: String,
     pub rating: AtcRating,
     pub protocol: ProtocolRevision,
@@ -68,38 +430,37 @@ impl Display for AtcRegisterMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AtcRegisterMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 7);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for AtcRegisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 7..=7);
+        let first = parse_sender(cx, fields, 3, "from")?;
         Ok(AtcRegisterMessage::new(
             first,
-            fields[1],
+            parse_recipient(cx, fields, 1)?,
             fields[2],
-            fields[3],
+            parse_identifier(cx, fields, 3, "cid")?,
             fields[4],
-            fields[5].parse()?,
-            fields.get(6).unwrap_or(&"9").parse()?,
+            parse_field(fields, 5, "rating")?,
+            parse_field(fields, 6, "protocol")?,
         ))
     }
 }
 
 impl AtcRegisterMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         real_name: impl Into<String>,
-        cid: impl Into<String>,
+        cid: Cid,
         password: impl Into<String>,
         rating: AtcRating,
         protocol: ProtocolRevision,
     ) -> Self {
         AtcRegisterMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             real_name: real_name.into(),
-            cid: cid.into(),
+            cid,
             password: password.into(),
             rating,
             protocol,
@@ -108,11 +469,12 @@ impl AtcRegisterMessage {
 }
 
 /// Sent by a pilot client to register itself on the network after the initial handshake
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PilotRegisterMessage {
-    pub from: String,
-    pub to: String,
-    pub cid: String,
+    pub from: Callsign,
+    pub to: Recipient,
+    pub cid: Cid,
     pub password: String,
     pub rating: PilotRating,
     pub protocol: ProtocolRevision,
@@ -136,39 +498,42 @@ impl Display for PilotRegisterMessage {
         )
     }
 }
-impl TryFrom<&[&str]> for PilotRegisterMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 7);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for PilotRegisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 7..=8);
+        // The real name is optional on VATSIM, but required by the legacy server
+        if cx.dialect() == Dialect::Classic {
+            check_min_num_fields!(fields, 8);
+        }
+        let first = parse_sender(cx, fields, 3, "from")?;
         Ok(PilotRegisterMessage::new(
             first,
-            fields[1],
+            parse_recipient(cx, fields, 1)?,
             *fields.get(7).unwrap_or(&""),
-            fields[2],
+            parse_identifier(cx, fields, 2, "cid")?,
             fields[3],
-            fields[4].parse()?,
-            fields[5].parse()?,
+            parse_field(fields, 4, "rating")?,
+            parse_field(fields, 5, "protocol")?,
             fields[6].into(),
         ))
     }
 }
 impl PilotRegisterMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         real_name: impl Into<String>,
-        cid: impl Into<String>,
+        cid: Cid,
         password: impl Into<String>,
         rating: PilotRating,
         protocol: ProtocolRevision,
         simulator_type: SimulatorType,
     ) -> Self {
         PilotRegisterMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             simulator_type,
-            cid: cid.into(),
+            cid,
             password: password.into(),
             rating,
             protocol,
@@ -178,10 +543,11 @@ impl PilotRegisterMessage {
 }
 
 /// Sent by an ATC client before disconnecting
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtcDeregisterMessage {
-    pub from: String,
-    pub cid: Option<String>,
+    pub from: Callsign,
+    pub cid: Option<Cid>,
 }
 
 impl Display for AtcDeregisterMessage {
@@ -194,33 +560,33 @@ impl Display for AtcDeregisterMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AtcDeregisterMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 1);
-        let first = &fields[0][3..];
-        let cid = fields.get(1).map(|s| s.to_string());
-        Ok(AtcDeregisterMessage {
-            from: first.to_uppercase(),
-            cid,
-        })
+impl ParseFields<'_> for AtcDeregisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 1..=2);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        let cid = fields
+            .get(1)
+            .map(|cid| util::identifier(cx, cid, fields, 1, "cid"))
+            .transpose()?;
+        Ok(AtcDeregisterMessage { from: first, cid })
     }
 }
 
 impl AtcDeregisterMessage {
-    pub fn new(from: impl AsRef<str>, cid: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, cid: Cid) -> Self {
         AtcDeregisterMessage {
-            from: from.as_ref().to_uppercase(),
-            cid: Some(cid.into()),
+            from,
+            cid: Some(cid),
         }
     }
 }
 
 /// Sent by a pilot client before disconnecting
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PilotDeregisterMessage {
-    pub from: String,
-    pub cid: Option<String>,
+    pub from: Callsign,
+    pub cid: Option<Cid>,
 }
 
 impl Display for PilotDeregisterMessage {
@@ -233,32 +599,32 @@ impl Display for PilotDeregisterMessage {
     }
 }
 
-impl TryFrom<&[&str]> for PilotDeregisterMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 1);
-        let first = &fields[0][3..];
-        let cid = fields.get(1).map(|s| s.to_string());
-        Ok(PilotDeregisterMessage {
-            from: first.to_uppercase(),
-            cid,
-        })
+impl ParseFields<'_> for PilotDeregisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 1..=2);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        let cid = fields
+            .get(1)
+            .map(|cid| util::identifier(cx, cid, fields, 1, "cid"))
+            .transpose()?;
+        Ok(PilotDeregisterMessage { from: first, cid })
     }
 }
 
 impl PilotDeregisterMessage {
-    pub fn new(from: impl AsRef<str>, cid: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, cid: Cid) -> Self {
         PilotDeregisterMessage {
-            from: from.as_ref().to_uppercase(),
-            cid: Some(cid.into()),
+            from,
+            cid: Some(cid),
         }
     }
 }
 
 /// Sent at regular intervals by an ATC client to update the server with its position
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtcPositionUpdateMessage {
-    pub callsign: String,
+    pub callsign: Callsign,
     pub frequencies: Vec<RadioFrequency>,
     pub atc_type: AtcType,
     pub vis_range: u32,
@@ -270,12 +636,11 @@ pub struct AtcPositionUpdateMessage {
 
 impl Display for AtcPositionUpdateMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let freqs_string = util::group_frequencies_without_symbol(&self.frequencies);
+        write!(f, "%{}:", self.callsign)?;
+        util::write_frequencies_without_symbol(f, &self.frequencies)?;
         write!(
             f,
-            "%{}:{}:{}:{}:{}:{:.5}:{:.5}:{}",
-            self.callsign,
-            freqs_string,
+            ":{}:{}:{}:{:.5}:{:.5}:{}",
             self.atc_type as u8,
             self.vis_range,
             self.rating as u8,
@@ -286,33 +651,16 @@ impl Display for AtcPositionUpdateMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AtcPositionUpdateMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 7);
-        let first = &fields[0][1..];
-        Ok(AtcPositionUpdateMessage::new(
-            first,
-            util::split_frequencies(fields[1]),
-            fields[2].parse()?,
-            fields[3]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidVisRange(fields[3].to_string()))?,
-            fields[4].parse()?,
-            fields[5]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[5].to_string()))?,
-            fields[6]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[6].to_string()))?,
-            fields.get(7).unwrap_or(&"0").parse().unwrap_or_default(),
-        ))
+impl ParseFields<'_> for AtcPositionUpdateMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        cx.parse::<AtcPositionUpdateRef>(fields)
+            .map(|m| m.to_owned())
     }
 }
 
 impl AtcPositionUpdateMessage {
     pub fn new(
-        callsign: impl AsRef<str>,
+        callsign: Callsign,
         frequencies: impl Into<Vec<RadioFrequency>>,
         atc_type: AtcType,
         vis_range: u32,
@@ -322,7 +670,7 @@ impl AtcPositionUpdateMessage {
         elevation: i32,
     ) -> Self {
         AtcPositionUpdateMessage {
-            callsign: callsign.as_ref().to_uppercase(),
+            callsign,
             frequencies: frequencies.into(),
             atc_type,
             vis_range,
@@ -334,9 +682,10 @@ impl AtcPositionUpdateMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtcSecondaryVisCentreMessage {
-    pub callsign: String,
+    pub callsign: Callsign,
     pub index: usize,
     pub latitude: f64,
     pub longitude: f64,
@@ -352,30 +701,33 @@ impl Display for AtcSecondaryVisCentreMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AtcSecondaryVisCentreMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 4);
-        let first = &fields[0][1..];
+impl ParseFields<'_> for AtcSecondaryVisCentreMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 4..=4);
+        let first = parse_sender(cx, fields, 1, "callsign")?;
         Ok(AtcSecondaryVisCentreMessage::new(
             first,
-            fields[1]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidIndex(fields[1].to_string()))?,
-            fields[2]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[5].to_string()))?,
-            fields[3]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[5].to_string()))?,
+            parse_field_or(fields, 1, "index", FsdMessageParseError::InvalidIndex)?,
+            parse_field_or(
+                fields,
+                2,
+                "latitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
+            parse_field_or(
+                fields,
+                3,
+                "longitude",
+                FsdMessageParseError::InvalidCoordinate,
+            )?,
         ))
     }
 }
 
 impl AtcSecondaryVisCentreMessage {
-    pub fn new(callsign: impl AsRef<str>, index: usize, latitude: f64, longitude: f64) -> Self {
+    pub fn new(callsign: Callsign, index: usize, latitude: f64, longitude: f64) -> Self {
         AtcSecondaryVisCentreMessage {
-            callsign: callsign.as_ref().to_uppercase(),
+            callsign,
             index,
             latitude,
             longitude,
@@ -384,9 +736,10 @@ impl AtcSecondaryVisCentreMessage {
 }
 
 /// Sent at regular intervals by a pilot client to update the server with its position
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PilotPositionUpdateMessage {
-    pub callsign: String,
+    pub callsign: Callsign,
     pub transponder_mode: TransponderMode,
     pub transponder_code: TransponderCode,
     pub rating: PilotRating,
@@ -403,12 +756,27 @@ pub struct PilotPositionUpdateMessage {
 
 impl Display for PilotPositionUpdateMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_position(f)?;
+        let alt_diff = self.pressure_altitude - self.true_altitude;
+        write!(f, ":{}", alt_diff as i32)
+    }
+}
+
+impl ParseFields<'_> for PilotPositionUpdateMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        cx.parse::<PilotPositionUpdateRef>(fields)
+            .map(|m| m.to_owned())
+    }
+}
+
+impl PilotPositionUpdateMessage {
+    /// Writes every field up to the altitude difference, which IVAO clients leave off
+    pub(crate) fn write_position(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
         let pbh =
             util::encode_pitch_bank_heading(self.pitch, self.bank, self.heading, self.on_ground);
-        let alt_diff = self.pressure_altitude - self.true_altitude;
         write!(
             f,
-            "@{}:{}:{}:{}:{:.5}:{:.5}:{}:{}:{}:{}",
+            "@{}:{}:{}:{}:{:.5}:{:.5}:{}:{}:{}",
             self.transponder_mode,
             self.callsign,
             self.transponder_code,
@@ -418,57 +786,11 @@ impl Display for PilotPositionUpdateMessage {
             self.true_altitude as i32,
             self.ground_speed,
             pbh,
-            alt_diff as i32,
         )
     }
-}
-
-impl TryFrom<&[&str]> for PilotPositionUpdateMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 10);
-        let first = &fields[0][1..];
-
-        let true_altitude = fields[6]
-            .parse()
-            .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[6].to_string()))?;
-        let alt_diff: f64 = fields[9]
-            .parse()
-            .map_err(|_| FsdMessageParseError::InvalidAltitudeDifference(fields[9].to_string()))?;
-        let (pitch, bank, heading, on_ground) = {
-            let pbh = fields[8].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPitchBankHeading(fields[8].to_string())
-            })?;
-            util::decode_pitch_bank_heading(pbh)
-        };
 
-        Ok(PilotPositionUpdateMessage::new(
-            fields[1],
-            first.parse()?,
-            fields[2].parse()?,
-            fields[3].parse()?,
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[4].to_string()))?,
-            fields[5]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[5].to_string()))?,
-            true_altitude,
-            true_altitude + alt_diff,
-            fields[7]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidSpeed(fields[7].to_string()))?,
-            pitch,
-            bank,
-            heading,
-            on_ground,
-        ))
-    }
-}
-
-impl PilotPositionUpdateMessage {
     pub fn new(
-        callsign: impl AsRef<str>,
+        callsign: Callsign,
         transponder_mode: TransponderMode,
         transponder_code: TransponderCode,
         rating: PilotRating,
@@ -483,7 +805,7 @@ impl PilotPositionUpdateMessage {
         on_ground: bool,
     ) -> Self {
         PilotPositionUpdateMessage {
-            callsign: callsign.as_ref().to_uppercase(),
+            callsign,
             transponder_mode,
             transponder_code,
             rating,
@@ -500,10 +822,11 @@ impl PilotPositionUpdateMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AuthenticationChallengeMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub challenge: String,
 }
 
@@ -513,31 +836,33 @@ impl Display for AuthenticationChallengeMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AuthenticationChallengeMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for AuthenticationChallengeMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
         Ok(AuthenticationChallengeMessage::new(
-            first, fields[1], fields[2],
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
         ))
     }
 }
 
 impl AuthenticationChallengeMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, challenge: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, challenge: impl Into<String>) -> Self {
         AuthenticationChallengeMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             challenge: challenge.into(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AuthenticationResponseMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub response: String,
 }
 
@@ -547,31 +872,33 @@ impl Display for AuthenticationResponseMessage {
     }
 }
 
-impl TryFrom<&[&str]> for AuthenticationResponseMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for AuthenticationResponseMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
         Ok(AuthenticationResponseMessage::new(
-            first, fields[1], fields[2],
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
         ))
     }
 }
 
 impl AuthenticationResponseMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, response: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, response: impl Into<String>) -> Self {
         AuthenticationResponseMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             response: response.into(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub message: String,
 }
 impl Display for TextMessage {
@@ -579,11 +906,11 @@ impl Display for TextMessage {
         write!(f, "#TM{}:{}:{}", self.from, self.to, self.message)
     }
 }
-impl TryFrom<&[&str]> for TextMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for TextMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+        cx.check_trailing_colon(fields, 3)?;
+        let first = parse_sender(cx, fields, 3, "from")?;
         let mut message = fields[2].to_string();
         if fields.len() > 3 {
             for m in &fields[3..] {
@@ -591,39 +918,45 @@ impl TryFrom<&[&str]> for TextMessage {
                 message.push_str(m);
             }
         }
-        Ok(TextMessage::new(first, fields[1], message))
+        Ok(TextMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            message,
+        ))
     }
 }
 
 impl TextMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, message: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, message: impl Into<String>) -> Self {
         TextMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             message: message.into(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FrequencyMessage {
-    pub from: String,
+    pub from: Callsign,
     pub to: Vec<RadioFrequency>,
     pub message: String,
 }
 
 impl Display for FrequencyMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let frequencies = util::group_frequencies_with_symbol(&self.to);
-        write!(f, "#TM{}:{}:{}", self.from, frequencies, self.message)
+        write!(f, "#TM{}:", self.from)?;
+        util::write_frequencies_with_symbol(f, &self.to)?;
+        write!(f, ":{}", self.message)
     }
 }
 
-impl TryFrom<&[&str]> for FrequencyMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for FrequencyMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+        cx.check_trailing_colon(fields, 3)?;
+        let first = parse_sender(cx, fields, 3, "from")?;
         let mut message = fields[2].to_string();
         if fields.len() > 3 {
             for m in &fields[3..] {
@@ -641,22 +974,23 @@ impl TryFrom<&[&str]> for FrequencyMessage {
 
 impl FrequencyMessage {
     pub fn new(
-        from: impl AsRef<str>,
+        from: Callsign,
         to: impl Into<Vec<RadioFrequency>>,
         message: impl Into<String>,
     ) -> Self {
         FrequencyMessage {
-            from: from.as_ref().to_uppercase(),
+            from,
             to: to.into(),
             message: message.into(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChangeServerMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub hostname: String,
 }
 
@@ -666,30 +1000,34 @@ impl Display for ChangeServerMessage {
     }
 }
 
-impl TryFrom<&[&str]> for ChangeServerMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for ChangeServerMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(ChangeServerMessage::new(first, fields[1], fields[2]))
+        Ok(ChangeServerMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+        ))
     }
 }
 
 impl ChangeServerMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, hostname: impl Into<String>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, hostname: impl Into<String>) -> Self {
         ChangeServerMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             hostname: hostname.into(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InitialServerHandshakeMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub version: String,
     pub initial_key: String,
 }
@@ -704,50 +1042,54 @@ impl Display for InitialServerHandshakeMessage {
     }
 }
 
-impl TryFrom<&[&str]> for InitialServerHandshakeMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for InitialServerHandshakeMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 4..=4);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
         Ok(InitialServerHandshakeMessage::new(
-            first, fields[1], fields[2], fields[3],
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+            fields[3],
         ))
     }
 }
 
 impl InitialServerHandshakeMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         version: impl Into<String>,
         initial_key: impl Into<String>,
     ) -> Self {
         InitialServerHandshakeMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             version: version.into(),
             initial_key: initial_key.into(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InitialClientHandshakeMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub client_id: u16,
     pub client_name: String,
     pub major_version: u32,
     pub minor_version: u32,
-    pub cid: String,
+    pub cid: Cid,
     pub guid: String,
     pub initial_key: Option<String>,
 }
 
 impl Display for InitialClientHandshakeMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut message = format!(
+        write!(
+            f,
             "$ID{}:{}:{:04x}:{}:{}:{}:{}:{}",
             self.from,
             self.to,
@@ -757,34 +1099,43 @@ impl Display for InitialClientHandshakeMessage {
             self.minor_version,
             self.cid,
             self.guid
-        );
+        )?;
         if let Some(initial_key) = &self.initial_key {
-            message.push(':');
-            message.push_str(initial_key);
+            write!(f, ":{initial_key}")?;
         }
-        write!(f, "{message}")
+        Ok(())
     }
 }
 
-impl TryFrom<&[&str]> for InitialClientHandshakeMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 8);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for InitialClientHandshakeMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 8..=9);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
         Ok(InitialClientHandshakeMessage::new(
             first,
-            fields[1],
-            u16::from_str_radix(fields[2], 16)
-                .map_err(|_| FsdMessageParseError::InvalidClientID(fields[2].to_string()))?,
+            parse_recipient(cx, fields, 1)?,
+            u16::from_str_radix(fields[2], 16).map_err(|_| {
+                FsdMessageParseError::InvalidClientID(fields[2].to_string()).at_field(
+                    fields,
+                    2,
+                    "client_id",
+                )
+            })?,
             fields[3],
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidVersionNumber(fields[4].to_string()))?,
-            fields[5]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidVersionNumber(fields[5].to_string()))?,
-            fields[6],
+            parse_field_or(
+                fields,
+                4,
+                "major_version",
+                FsdMessageParseError::InvalidVersionNumber,
+            )?,
+            parse_field_or(
+                fields,
+                5,
+                "minor_version",
+                FsdMessageParseError::InvalidVersionNumber,
+            )?,
+            parse_identifier(cx, fields, 6, "cid")?,
             fields[7],
             fields.get(8).copied(),
         ))
@@ -793,34 +1144,35 @@ impl TryFrom<&[&str]> for InitialClientHandshakeMessage {
 
 impl InitialClientHandshakeMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         client_id: u16,
         client_name: impl Into<String>,
         major_version: u32,
         minor_version: u32,
-        cid: impl Into<String>,
+        cid: Cid,
         guid: impl Into<String>,
         initial_key: Option<impl Into<String>>,
     ) -> Self {
         InitialClientHandshakeMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             client_id,
             client_name: client_name.into(),
             major_version,
             minor_version,
-            cid: cid.into(),
+            cid,
             guid: guid.into(),
             initial_key: initial_key.map(|x| x.into()),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SendFastPositionUpdatesMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub send_fast: bool,
 }
 
@@ -830,33 +1182,33 @@ impl Display for SendFastPositionUpdatesMessage {
     }
 }
 
-impl TryFrom<&[&str]> for SendFastPositionUpdatesMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for SendFastPositionUpdatesMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
         Ok(SendFastPositionUpdatesMessage::new(
             first,
-            fields[1],
+            parse_recipient(cx, fields, 1)?,
             fields[2] == "1",
         ))
     }
 }
 
 impl SendFastPositionUpdatesMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, send_fast: bool) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, send_fast: bool) -> Self {
         SendFastPositionUpdatesMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             send_fast,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VelocityPositionStoppedMessage {
-    pub from: String,
+    pub from: Callsign,
     pub latitude: f64,
     pub longitude: f64,
     pub true_altitude: f64,
@@ -884,49 +1236,16 @@ impl Display for VelocityPositionStoppedMessage {
     }
 }
 
-impl TryFrom<&[&str]> for VelocityPositionStoppedMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 6);
-        let first = &fields[0][3..];
-        let pbh = fields[5]
-            .parse::<u32>()
-            .map_err(|_| FsdMessageParseError::InvalidPitchBankHeading(fields[5].to_string()))?;
-        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(pbh);
-        let nga =
-            if let Some(nga) = fields.get(6) {
-                Some(nga.parse::<f64>().map_err(|_| {
-                    FsdMessageParseError::InvalidNosewheelAngle(fields[6].to_string())
-                })?)
-            } else {
-                None
-            };
-        Ok(VelocityPositionStoppedMessage::new(
-            first,
-            fields[1]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[1].to_string()))?,
-            fields[2]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[2].to_string()))?,
-            fields[3]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[3].to_string()))?,
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[4].to_string()))?,
-            pitch,
-            bank,
-            heading,
-            on_ground,
-            nga,
-        ))
+impl ParseFields<'_> for VelocityPositionStoppedMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        cx.parse::<VelocityPositionStoppedRef>(fields)
+            .map(|m| m.to_owned())
     }
 }
 
 impl VelocityPositionStoppedMessage {
     pub fn new(
-        from: impl AsRef<str>,
+        from: Callsign,
         latitude: f64,
         longitude: f64,
         true_altitude: f64,
@@ -938,7 +1257,7 @@ impl VelocityPositionStoppedMessage {
         nose_gear_angle: Option<f64>,
     ) -> Self {
         VelocityPositionStoppedMessage {
-            from: from.as_ref().to_uppercase(),
+            from,
             latitude,
             longitude,
             true_altitude,
@@ -952,9 +1271,10 @@ impl VelocityPositionStoppedMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VelocityPositionSlowMessage {
-    pub from: String,
+    pub from: Callsign,
     pub latitude: f64,
     pub longitude: f64,
     pub true_altitude: f64,
@@ -999,68 +1319,15 @@ impl Display for VelocityPositionSlowMessage {
     }
 }
 
-impl TryFrom<&[&str]> for VelocityPositionSlowMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 12);
-
-        let first = &fields[0][3..];
-        let pbh = fields[5]
-            .parse::<u32>()
-            .map_err(|_| FsdMessageParseError::InvalidPitchBankHeading(fields[5].to_string()))?;
-        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(pbh);
-        let nga =
-            if let Some(nga) = fields.get(12) {
-                Some(nga.parse::<f64>().map_err(|_| {
-                    FsdMessageParseError::InvalidNosewheelAngle(fields[12].to_string())
-                })?)
-            } else {
-                None
-            };
-        Ok(VelocityPositionSlowMessage::new(
-            first,
-            fields[1]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[1].to_string()))?,
-            fields[2]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[2].to_string()))?,
-            fields[3]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[3].to_string()))?,
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[4].to_string()))?,
-            pitch,
-            bank,
-            heading,
-            on_ground,
-            fields[6].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[6].to_string())
-            })?,
-            fields[7].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[7].to_string())
-            })?,
-            fields[8].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[8].to_string())
-            })?,
-            fields[9].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[9].to_string())
-            })?,
-            fields[10].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[10].to_string())
-            })?,
-            fields[11].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[11].to_string())
-            })?,
-            nga,
-        ))
+impl ParseFields<'_> for VelocityPositionSlowMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        VelocityPositionRef::parse_fields(fields, 3, cx).map(|m| m.to_owned())
     }
 }
 
 impl VelocityPositionSlowMessage {
     pub fn new(
-        from: impl AsRef<str>,
+        from: Callsign,
         latitude: f64,
         longitude: f64,
         true_altitude: f64,
@@ -1078,7 +1345,7 @@ impl VelocityPositionSlowMessage {
         nose_gear_angle: Option<f64>,
     ) -> Self {
         VelocityPositionSlowMessage {
-            from: from.as_ref().to_uppercase(),
+            from,
             latitude,
             longitude,
             true_altitude,
@@ -1098,9 +1365,10 @@ impl VelocityPositionSlowMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VelocityPositionFastMessage {
-    pub from: String,
+    pub from: Callsign,
     pub latitude: f64,
     pub longitude: f64,
     pub true_altitude: f64,
@@ -1168,68 +1436,15 @@ impl Display for VelocityPositionFastMessage {
     }
 }
 
-impl TryFrom<&[&str]> for VelocityPositionFastMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 12);
-
-        let first = &fields[0][1..];
-        let pbh = fields[5]
-            .parse::<u32>()
-            .map_err(|_| FsdMessageParseError::InvalidPitchBankHeading(fields[5].to_string()))?;
-        let (pitch, bank, heading, on_ground) = util::decode_pitch_bank_heading(pbh);
-        let nga =
-            if let Some(nga) = fields.get(12) {
-                Some(nga.parse::<f64>().map_err(|_| {
-                    FsdMessageParseError::InvalidNosewheelAngle(fields[12].to_string())
-                })?)
-            } else {
-                None
-            };
-        Ok(VelocityPositionFastMessage::new(
-            first,
-            fields[1]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[1].to_string()))?,
-            fields[2]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidCoordinate(fields[2].to_string()))?,
-            fields[3]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[3].to_string()))?,
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidAltitude(fields[4].to_string()))?,
-            pitch,
-            bank,
-            heading,
-            on_ground,
-            fields[6].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[6].to_string())
-            })?,
-            fields[7].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[7].to_string())
-            })?,
-            fields[8].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[8].to_string())
-            })?,
-            fields[9].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[9].to_string())
-            })?,
-            fields[10].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[10].to_string())
-            })?,
-            fields[11].parse().map_err(|_| {
-                FsdMessageParseError::InvalidPositionVelocity(fields[11].to_string())
-            })?,
-            nga,
-        ))
+impl ParseFields<'_> for VelocityPositionFastMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        VelocityPositionRef::parse_fields(fields, 1, cx).map(Into::into)
     }
 }
 
 impl VelocityPositionFastMessage {
     pub fn new(
-        from: impl AsRef<str>,
+        from: Callsign,
         latitude: f64,
         longitude: f64,
         true_altitude: f64,
@@ -1247,7 +1462,7 @@ impl VelocityPositionFastMessage {
         nose_gear_angle: Option<f64>,
     ) -> Self {
         VelocityPositionFastMessage {
-            from: from.as_ref().to_uppercase(),
+            from,
             latitude,
             longitude,
             true_altitude,
@@ -1267,52 +1482,56 @@ impl VelocityPositionFastMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KillMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub reason: Option<String>,
 }
 
 impl Display for KillMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut message = format!("$!!{}:{}", self.from, self.to);
+        write!(f, "$!!{}:{}", self.from, self.to)?;
         if let Some(reason) = &self.reason {
-            message.push(':');
-            message.push_str(reason);
+            write!(f, ":{reason}")?;
         };
-        write!(f, "{}", message)
+        Ok(())
     }
 }
 
-impl TryFrom<&[&str]> for KillMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 2);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for KillMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 2..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(KillMessage::new(first, fields[1], fields.get(2).copied()))
+        Ok(KillMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields.get(2).copied(),
+        ))
     }
 }
 
 impl KillMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         reason: Option<impl Into<String>>,
     ) -> Self {
         KillMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             reason: reason.map(|x| x.into()),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetarRequestMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub station: String,
 }
 
@@ -1322,30 +1541,34 @@ impl Display for MetarRequestMessage {
     }
 }
 
-impl TryFrom<&[&str]> for MetarRequestMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 4);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for MetarRequestMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 4..=4);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(MetarRequestMessage::new(first, fields[1], fields[3]))
+        Ok(MetarRequestMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[3],
+        ))
     }
 }
 
 impl MetarRequestMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, station: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, station: impl AsRef<str>) -> Self {
         MetarRequestMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             station: station.as_ref().to_uppercase(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetarResponseMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub metar: String,
 }
 
@@ -1355,30 +1578,442 @@ impl Display for MetarResponseMessage {
     }
 }
 
-impl TryFrom<&[&str]> for MetarResponseMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 4);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for MetarResponseMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 4..=4);
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(MetarResponseMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[3],
+        ))
+    }
+}
+
+impl MetarResponseMessage {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, metar: impl AsRef<str>) -> Self {
+        MetarResponseMessage {
+            from,
+            to: to.into(),
+            metar: metar.as_ref().to_uppercase(),
+        }
+    }
+}
+
+/// IVAO only. An ATC registration, with the client software details that IVAO clients append to it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IvaoAtcRegisterMessage {
+    pub registration: AtcRegisterMessage,
+    pub software: String,
+    pub version: String,
+}
+
+impl Display for IvaoAtcRegisterMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.registration, self.software, self.version
+        )
+    }
+}
+
+impl ParseFields<'_> for IvaoAtcRegisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 9..=9);
+        Ok(IvaoAtcRegisterMessage::new(
+            cx.parse(&fields[..7])?,
+            fields[7],
+            fields[8],
+        ))
+    }
+}
+
+impl IvaoAtcRegisterMessage {
+    pub fn new(
+        registration: AtcRegisterMessage,
+        software: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        IvaoAtcRegisterMessage {
+            registration,
+            software: software.into(),
+            version: version.into(),
+        }
+    }
+}
+
+/// IVAO only. A pilot registration, with the client software details that IVAO clients append to it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IvaoPilotRegisterMessage {
+    pub registration: PilotRegisterMessage,
+    pub software: String,
+    pub version: String,
+}
+
+impl Display for IvaoPilotRegisterMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.registration, self.software, self.version
+        )
+    }
+}
+
+impl ParseFields<'_> for IvaoPilotRegisterMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 10..=10);
+        Ok(IvaoPilotRegisterMessage::new(
+            cx.parse(&fields[..8])?,
+            fields[8],
+            fields[9],
+        ))
+    }
+}
+
+impl IvaoPilotRegisterMessage {
+    pub fn new(
+        registration: PilotRegisterMessage,
+        software: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        IvaoPilotRegisterMessage {
+            registration,
+            software: software.into(),
+            version: version.into(),
+        }
+    }
+}
+
+/// IVAO only. A client query of a type that only exists on IVAO, kept with its raw data fields
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IvaoClientQueryMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub query_type: String,
+    pub data: Vec<String>,
+}
+
+impl Display for IvaoClientQueryMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "$CQ{}:{}:{}", self.from, self.to, self.query_type)?;
+        for field in &self.data {
+            write!(f, ":{field}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseFields<'_> for IvaoClientQueryMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_min_num_fields!(fields, 3);
+        cx.check_trailing_colon(fields, 3)?;
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(IvaoClientQueryMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+            fields[3..].iter().map(|x| x.to_string()).collect(),
+        ))
+    }
+}
+
+impl IvaoClientQueryMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        query_type: impl AsRef<str>,
+        data: Vec<String>,
+    ) -> Self {
+        IvaoClientQueryMessage {
+            from,
+            to: to.into(),
+            query_type: query_type.as_ref().to_uppercase(),
+            data,
+        }
+    }
+}
+
+/// Classic FSD only. Sent by a client to request the weather profile for a station.
+/// The server replies with a [`TemperatureDataMessage`], a [`WindDataMessage`] and a [`CloudDataMessage`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeatherRequestMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub station: String,
+}
+
+impl Display for WeatherRequestMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#RW{}:{}:{}", self.from, self.to, self.station)
+    }
+}
+
+impl ParseFields<'_> for WeatherRequestMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(WeatherRequestMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+        ))
+    }
+}
+
+impl WeatherRequestMessage {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, station: impl AsRef<str>) -> Self {
+        WeatherRequestMessage {
+            from,
+            to: to.into(),
+            station: station.as_ref().to_uppercase(),
+        }
+    }
+}
+
+/// Classic FSD only. The temperature layers and pressure of a weather profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TemperatureDataMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub layers: [TemperatureLayer; 4],
+    /// Pressure in hundredths of an inch of mercury
+    pub barometer: u32,
+}
+
+impl Display for TemperatureDataMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#TD{}:{}", self.from, self.to)?;
+        for layer in &self.layers {
+            write!(f, ":{layer}")?;
+        }
+        write!(f, ":{}", self.barometer)
+    }
+}
+
+impl ParseFields<'_> for TemperatureDataMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 11..=11);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        let mut layers = [TemperatureLayer::default(); 4];
+        for (i, layer) in layers.iter_mut().enumerate() {
+            *layer = TemperatureLayer::from_fields(fields, 2 + i * 2)?;
+        }
+
+        Ok(TemperatureDataMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            layers,
+            parse_field_or(
+                fields,
+                10,
+                "barometer",
+                FsdMessageParseError::InvalidWeatherValue,
+            )?,
+        ))
+    }
+}
+
+impl TemperatureDataMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        layers: [TemperatureLayer; 4],
+        barometer: u32,
+    ) -> Self {
+        TemperatureDataMessage {
+            from,
+            to: to.into(),
+            layers,
+            barometer,
+        }
+    }
+}
+
+/// Classic FSD only. The wind layers of a weather profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindDataMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub layers: [WindLayer; 4],
+}
+
+impl Display for WindDataMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#WD{}:{}", self.from, self.to)?;
+        for layer in &self.layers {
+            write!(f, ":{layer}")?;
+        }
+        // The legacy server terminates the wind data with a colon
+        write!(f, ":")
+    }
+}
+
+impl ParseFields<'_> for WindDataMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 26..=26);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        let mut layers = [WindLayer::default(); 4];
+        for (i, layer) in layers.iter_mut().enumerate() {
+            *layer = WindLayer::from_fields(fields, 2 + i * 6)?;
+        }
+
+        Ok(WindDataMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            layers,
+        ))
+    }
+}
+
+impl WindDataMessage {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, layers: [WindLayer; 4]) -> Self {
+        WindDataMessage {
+            from,
+            to: to.into(),
+            layers,
+        }
+    }
+}
+
+/// Classic FSD only. The cloud layers and visibility of a weather profile.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CloudDataMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub layers: [CloudLayer; 2],
+    pub thunderstorm: CloudLayer,
+    /// Visibility in statute miles
+    pub visibility: f64,
+}
+
+impl Display for CloudDataMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#CD{}:{}:{}:{}:{}:{:.2}",
+            self.from, self.to, self.layers[0], self.layers[1], self.thunderstorm, self.visibility
+        )
+    }
+}
+
+impl ParseFields<'_> for CloudDataMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 18..=18);
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(CloudDataMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            [
+                CloudLayer::from_fields(fields, 2)?,
+                CloudLayer::from_fields(fields, 7)?,
+            ],
+            CloudLayer::from_fields(fields, 12)?,
+            parse_field_or(
+                fields,
+                17,
+                "visibility",
+                FsdMessageParseError::InvalidWeatherValue,
+            )?,
+        ))
+    }
+}
+
+impl CloudDataMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        layers: [CloudLayer; 2],
+        thunderstorm: CloudLayer,
+        visibility: f64,
+    ) -> Self {
+        CloudDataMessage {
+            from,
+            to: to.into(),
+            layers,
+            thunderstorm,
+            visibility,
+        }
+    }
+}
+
+/// Classic FSD only. Sent periodically by the server to vary the wind, simulating gusts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindDeltaMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub speed_delta: i16,
+    pub direction_delta: i16,
+}
+
+impl Display for WindDeltaMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#DL{}:{}:{}:{}",
+            self.from, self.to, self.speed_delta, self.direction_delta
+        )
+    }
+}
+
+impl ParseFields<'_> for WindDeltaMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 4..=4);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(MetarResponseMessage::new(first, fields[1], fields[3]))
+        Ok(WindDeltaMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            parse_field_or(
+                fields,
+                2,
+                "speed_delta",
+                FsdMessageParseError::InvalidWeatherValue,
+            )?,
+            parse_field_or(
+                fields,
+                3,
+                "direction_delta",
+                FsdMessageParseError::InvalidWeatherValue,
+            )?,
+        ))
     }
 }
 
-impl MetarResponseMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, metar: impl AsRef<str>) -> Self {
-        MetarResponseMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
-            metar: metar.as_ref().to_uppercase(),
+impl WindDeltaMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        speed_delta: i16,
+        direction_delta: i16,
+    ) -> Self {
+        WindDeltaMessage {
+            from,
+            to: to.into(),
+            speed_delta,
+            direction_delta,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PingMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub data: String,
 }
 
@@ -1388,30 +2023,34 @@ impl Display for PingMessage {
     }
 }
 
-impl TryFrom<&[&str]> for PingMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for PingMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(PingMessage::new(first, fields[1], fields[2]))
+        Ok(PingMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+        ))
     }
 }
 
 impl PingMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, data: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, data: impl AsRef<str>) -> Self {
         PingMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             data: data.as_ref().to_string(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PongMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub data: String,
 }
 
@@ -1421,30 +2060,34 @@ impl Display for PongMessage {
     }
 }
 
-impl TryFrom<&[&str]> for PongMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for PongMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(PongMessage::new(first, fields[1], fields[2]))
+        Ok(PongMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[2],
+        ))
     }
 }
 
 impl PongMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, data: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, data: impl AsRef<str>) -> Self {
         PongMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             data: data.as_ref().to_string(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaneInfoRequestMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
 }
 
 impl Display for PlaneInfoRequestMessage {
@@ -1453,29 +2096,32 @@ impl Display for PlaneInfoRequestMessage {
     }
 }
 
-impl TryFrom<&[&str]> for PlaneInfoRequestMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for PlaneInfoRequestMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
-        Ok(PlaneInfoRequestMessage::new(first, fields[1]))
+        Ok(PlaneInfoRequestMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+        ))
     }
 }
 
 impl PlaneInfoRequestMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>) -> Self {
         PlaneInfoRequestMessage {
-            from: from.as_ref().into(),
-            to: to.as_ref().into(),
+            from,
+            to: to.into(),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaneInfoResponseMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub plane_info: PlaneInfo,
 }
 
@@ -1485,37 +2131,170 @@ impl Display for PlaneInfoResponseMessage {
     }
 }
 
-impl TryFrom<&[&str]> for PlaneInfoResponseMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for PlaneInfoResponseMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 5);
-        let first = &fields[0][3..];
+        cx.check_trailing_colon(fields, 5)?;
+        let first = parse_sender(cx, fields, 3, "from")?;
         if fields[3] != "GEN" {
-            return Err(FsdMessageParseError::UnknownMessageType(fields.join(":")));
+            return Err(
+                FsdMessageParseError::UnknownMessageType(fields.join(":")).at_field(
+                    fields,
+                    3,
+                    "plane_info_type",
+                ),
+            );
         }
 
         Ok(PlaneInfoResponseMessage::new(
             first,
-            fields[1],
+            parse_recipient(cx, fields, 1)?,
             fields[4..].into(),
         ))
     }
 }
 
 impl PlaneInfoResponseMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, plane_info: PlaneInfo) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, plane_info: PlaneInfo) -> Self {
         PlaneInfoResponseMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             plane_info,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Deprecated plane information request sent by FSInn, which also carries the sender's own aircraft
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FSInnPlaneInformationRequestMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub airline_icao: String,
+    pub aircraft_icao: String,
+    pub combined_type: String,
+    pub model_string: String,
+}
+
+impl Display for FSInnPlaneInformationRequestMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#SB{}:{}:FSIPIR:0:{}:{}:::::{}:{}",
+            self.from,
+            self.to,
+            self.airline_icao,
+            self.aircraft_icao,
+            self.combined_type,
+            self.model_string
+        )
+    }
+}
+
+impl ParseFields<'_> for FSInnPlaneInformationRequestMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 6..=12);
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(FSInnPlaneInformationRequestMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[4],
+            fields[5],
+            fields.get(10).copied().unwrap_or_default(),
+            fields.get(11).copied().unwrap_or_default(),
+        ))
+    }
+}
+
+impl FSInnPlaneInformationRequestMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        airline_icao: impl AsRef<str>,
+        aircraft_icao: impl AsRef<str>,
+        combined_type: impl AsRef<str>,
+        model_string: impl Into<String>,
+    ) -> Self {
+        FSInnPlaneInformationRequestMessage {
+            from,
+            to: to.into(),
+            airline_icao: airline_icao.as_ref().to_uppercase(),
+            aircraft_icao: aircraft_icao.as_ref().to_uppercase(),
+            combined_type: combined_type.as_ref().to_uppercase(),
+            model_string: model_string.into(),
+        }
+    }
+}
+
+/// Deprecated plane information response sent by FSInn
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FSInnPlaneInformationResponseMessage {
+    pub from: Callsign,
+    pub to: Recipient,
+    pub airline_icao: String,
+    pub aircraft_icao: String,
+    pub combined_type: String,
+    pub model_string: String,
+}
+
+impl Display for FSInnPlaneInformationResponseMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#SB{}:{}:FSIPI:0:{}:{}:::::{}:{}",
+            self.from,
+            self.to,
+            self.airline_icao,
+            self.aircraft_icao,
+            self.combined_type,
+            self.model_string
+        )
+    }
+}
+
+impl ParseFields<'_> for FSInnPlaneInformationResponseMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 6..=12);
+        let first = parse_sender(cx, fields, 3, "from")?;
+
+        Ok(FSInnPlaneInformationResponseMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            fields[4],
+            fields[5],
+            fields.get(10).copied().unwrap_or_default(),
+            fields.get(11).copied().unwrap_or_default(),
+        ))
+    }
+}
+
+impl FSInnPlaneInformationResponseMessage {
+    pub fn new(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        airline_icao: impl AsRef<str>,
+        aircraft_icao: impl AsRef<str>,
+        combined_type: impl AsRef<str>,
+        model_string: impl Into<String>,
+    ) -> Self {
+        FSInnPlaneInformationResponseMessage {
+            from,
+            to: to.into(),
+            airline_icao: airline_icao.as_ref().to_uppercase(),
+            aircraft_icao: aircraft_icao.as_ref().to_uppercase(),
+            combined_type: combined_type.as_ref().to_uppercase(),
+            model_string: model_string.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FsdErrorMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub error_type: FsdError,
 }
 
@@ -1542,15 +2321,16 @@ impl Display for FsdErrorMessage {
     }
 }
 
-impl TryFrom<&[&str]> for FsdErrorMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_min_num_fields!(fields, 5);
-        let first = &fields[0][3..];
-        let error_type = match fields[2]
-            .parse::<u8>()
-            .map_err(|_| FsdMessageParseError::InvalidServerError(fields[2].to_string()))?
-        {
+impl ParseFields<'_> for FsdErrorMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 5..=5);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        let error_type = match parse_field_or::<u8>(
+            fields,
+            2,
+            "error_number",
+            FsdMessageParseError::InvalidServerError,
+        )? {
             1 => FsdError::CallsignInUse,
             2 => FsdError::InvalidCallsign,
             3 => FsdError::AlreadyRegistered,
@@ -1570,24 +2350,29 @@ impl TryFrom<&[&str]> for FsdErrorMessage {
             17 => FsdError::AuthTimeOut,
             _ => FsdError::Other(fields[4].to_string()),
         };
-        Ok(FsdErrorMessage::new(first, fields[1], error_type))
+        Ok(FsdErrorMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            error_type,
+        ))
     }
 }
 
 impl FsdErrorMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, error_type: FsdError) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, error_type: FsdError) -> Self {
         FsdErrorMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             error_type,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlightPlanMessage {
-    pub to: String,
-    pub callsign: String,
+    pub to: Recipient,
+    pub callsign: Callsign,
     pub flight_plan: FlightPlan,
 }
 
@@ -1600,35 +2385,36 @@ impl Display for FlightPlanMessage {
     }
 }
 
-impl TryFrom<&[&str]> for FlightPlanMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_exact_num_fields!(fields, 17);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for FlightPlanMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_exact_num_fields!(cx, fields, 17);
+        let first = parse_sender(cx, fields, 3, "from")?;
 
         Ok(FlightPlanMessage::new(
-            fields[1],
+            parse_recipient(cx, fields, 1)?,
             first,
-            fields[2..].try_into()?,
+            cx.parse::<FlightPlan>(&fields[2..17])
+                .map_err(|e| e.offset_fields(fields, 2))?,
         ))
     }
 }
 
 impl FlightPlanMessage {
-    pub fn new(to: impl AsRef<str>, callsign: impl AsRef<str>, flight_plan: FlightPlan) -> Self {
+    pub fn new(to: impl Into<Recipient>, callsign: Callsign, flight_plan: FlightPlan) -> Self {
         FlightPlanMessage {
-            to: to.as_ref().to_uppercase(),
-            callsign: callsign.as_ref().to_uppercase(),
+            to: to.into(),
+            callsign,
             flight_plan,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlightPlanAmendmentMessage {
-    pub from: String,
-    pub to: String,
-    pub callsign: String,
+    pub from: Callsign,
+    pub to: Recipient,
+    pub callsign: Callsign,
     pub flight_plan: FlightPlan,
 }
 
@@ -1645,41 +2431,42 @@ impl Display for FlightPlanAmendmentMessage {
     }
 }
 
-impl TryFrom<&[&str]> for FlightPlanAmendmentMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        check_exact_num_fields!(fields, 18);
-        let first = &fields[0][3..];
+impl ParseFields<'_> for FlightPlanAmendmentMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_exact_num_fields!(cx, fields, 18);
+        let first = parse_sender(cx, fields, 3, "from")?;
         Ok(FlightPlanAmendmentMessage::new(
             first,
-            fields[1],
-            fields[2],
-            fields[3..].try_into()?,
+            parse_recipient(cx, fields, 1)?,
+            parse_identifier(cx, fields, 2, "callsign")?,
+            cx.parse::<FlightPlan>(&fields[3..18])
+                .map_err(|e| e.offset_fields(fields, 3))?,
         ))
     }
 }
 
 impl FlightPlanAmendmentMessage {
     pub fn new(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        callsign: Callsign,
         flight_plan: FlightPlan,
     ) -> Self {
         FlightPlanAmendmentMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
-            callsign: callsign.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
+            callsign,
             flight_plan,
         }
     }
 }
 
 #[non_exhaustive]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClientQueryMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub query_type: ClientQueryType,
 }
 
@@ -1688,66 +2475,75 @@ impl Display for ClientQueryMessage {
         write!(f, "$CQ{}:{}:{}", self.from, self.to, self.query_type)
     }
 }
-impl TryFrom<&[&str]> for ClientQueryMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for ClientQueryMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 3);
-        let first = &fields[0][3..];
+        let first = parse_sender(cx, fields, 3, "from")?;
+        // The most fields each query type has. Unknown types are rejected below, and aircraft configurations are
+        // JSON, which contains colons.
+        let max_fields = match fields[2] {
+            "C?" | "IP" | "ATIS" | "RN" | "SV" | "BY" | "CAPS" | "HI" | "INF" => 3,
+            "HLP" | "NOHLP" | "ATC" | "FP" | "WH" | "DR" | "IT" | "SIMTIME" | "NEWINFO" => 4,
+            "SC" | "FA" | "BC" | "VT" | "TA" | "HT" | "GD" | "NEWATIS" => 5,
+            "IPC" => 6,
+            "EST" => 7,
+            _ => fields.len(),
+        };
+        cx.check_max_fields(fields, max_fields)?;
         match fields[2] {
             "C?" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::Com1Freq,
             )),
             "IP" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::PublicIP,
             )),
             "ATIS" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::ATIS,
             )),
             "RN" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::RealName,
             )),
             "IPC" => {
                 //$CQserver:N194Q:IPC:W:852:8704
-                let remainder = fields
-                    .get(3..6)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(6, 3))?;
-                if remainder[0] != "W" || remainder[1] != "852" {
+                check_min_num_fields!(fields, 6);
+                if fields[3] != "W" || fields[4] != "852" {
                     return Err(FsdMessageParseError::UnknownMessageType(format!(
                         "IPC:{}:{}:{}",
                         fields[0], fields[1], fields[2]
-                    )));
+                    ))
+                    .at_field(fields, 3, "ipc_type"));
                 }
-                let code = TransponderCode::try_from_bcd_format(remainder[2])?;
+                let code = TransponderCode::try_from_bcd_format(fields[5])
+                    .map_err(|e| e.at_field(fields, 5, "code"))?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::ForceBeaconCode { code },
                 ))
             }
             "SV" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::Server,
             )),
             "ACC" => {
-                let data = fields
-                    .get(3)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(4, 3))?;
+                let data = field(fields, 3, "aircraft_config")?;
                 if data.contains("request") {
                     Ok(ClientQueryMessage::new(
                         first,
-                        fields[1],
+                        parse_recipient(cx, fields, 1)?,
                         ClientQueryType::AircraftConfigurationRequest,
                     ))
                 } else {
+                    cx.check_trailing_colon(fields, 4)?;
                     let data = {
                         let mut data_string = String::new();
                         let mut fields_peekable = fields[3..].iter().peekable();
@@ -1761,105 +2557,108 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
                     };
                     Ok(ClientQueryMessage::new(
                         first,
-                        fields[1],
+                        parse_recipient(cx, fields, 1)?,
                         ClientQueryType::AircraftConfigurationResponse {
-                            aircraft_config: data.as_str().parse()?,
+                            aircraft_config: data.as_str().parse().map_err(
+                                |e: FsdMessageParseError| e.at_field(fields, 3, "aircraft_config"),
+                            )?,
                         },
                     ))
                 }
             }
             "BY" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::RequestRelief,
             )),
             "HLP" => {
                 let mut message = fields.get(3).map(|s| s.to_string());
-                if let Some(ref msg) = message {
-                    if msg.is_empty() {
-                        message = None;
-                    }
+                if let Some(ref msg) = message
+                    && msg.is_empty()
+                {
+                    message = None;
                 }
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::HelpRequest { message },
                 ))
             }
             "NOHLP" => {
                 let mut message = fields.get(3).map(|s| s.to_string());
-                if let Some(ref msg) = message {
-                    if msg.is_empty() {
-                        message = None;
-                    }
+                if let Some(ref msg) = message
+                    && msg.is_empty()
+                {
+                    message = None;
                 }
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::CancelHelpRequest { message },
                 ))
             }
             "SC" => {
                 check_min_num_fields!(fields, 5);
-                let contents = fields[4].parse()?;
+                let contents = parse_field(fields, 4, "contents")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetScratchpad {
-                        aircraft_callsign: fields[3].to_uppercase(),
+                        aircraft_callsign: parse_identifier(cx, fields, 3, "aircraft_callsign")?,
                         contents,
                     },
                 ))
             }
             "FA" => {
                 check_min_num_fields!(fields, 5);
-                let level = fields[4].parse()?;
+                let level = util::parse_level(cx, fields, 4, "level")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetFinalAltitude {
-                        aircraft_callsign: fields[3].to_uppercase(),
+                        aircraft_callsign: parse_identifier(cx, fields, 3, "aircraft_callsign")?,
                         level,
                     },
                 ))
             }
             "BC" => {
                 check_min_num_fields!(fields, 5);
-                let code = fields[4].parse()?;
+                let code = parse_field(fields, 4, "code")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetBeaconCode {
-                        aircraft_callsign: fields[3].to_uppercase(),
+                        aircraft_callsign: parse_identifier(cx, fields, 3, "aircraft_callsign")?,
                         code,
                     },
                 ))
             }
             "ATC" => {
-                let atc_callsign = fields.get(3).unwrap_or(&first).to_uppercase();
+                let atc_callsign = match fields.get(3) {
+                    Some(_) => parse_identifier(cx, fields, 3, "atc_callsign")?,
+                    None => first,
+                };
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::IsValidATC { atc_callsign },
                 ))
             }
             "FP" => {
-                let aircraft_callsign = fields
-                    .get(3)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(4, 3))?
-                    .to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::FlightPlan { aircraft_callsign },
                 ))
             }
             "NEWATIS" => {
                 check_min_num_fields!(fields, 5);
-                let (atis_letter, surface_wind, pressure) = util::parse_new_atis(&fields[3..])?;
+                let (atis_letter, surface_wind, pressure) =
+                    util::parse_new_atis(&fields[3..]).map_err(|e| e.offset_fields(fields, 3))?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::NewATIS {
                         atis_letter,
                         surface_wind,
@@ -1881,10 +2680,16 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
                             Some(c)
                         }
                     })
-                    .ok_or_else(|| FsdMessageParseError::InvalidATISLine(fields.join(":")))?;
+                    .ok_or_else(|| {
+                        FsdMessageParseError::InvalidATISLine(fields.join(":")).at_field(
+                            fields,
+                            3,
+                            "atis_letter",
+                        )
+                    })?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::NewInfo { atis_letter },
                 ))
             }
@@ -1892,29 +2697,29 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
                 check_min_num_fields!(fields, 5);
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetVoiceType {
-                        aircraft_callsign: fields[3].to_uppercase(),
+                        aircraft_callsign: parse_identifier(cx, fields, 3, "aircraft_callsign")?,
                         voice_capability: fields[4].into(),
                     },
                 ))
             }
             "WH" => {
                 check_min_num_fields!(fields, 4);
-                let aircraft_callsign = fields[3].to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::WhoHas { aircraft_callsign },
                 ))
             }
             "TA" => {
                 check_min_num_fields!(fields, 5);
-                let aircraft_callsign = fields[3].to_uppercase();
-                let level = fields[4].parse()?;
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
+                let level = util::parse_level(cx, fields, 4, "level")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetTempAltitude {
                         aircraft_callsign,
                         level,
@@ -1923,11 +2728,11 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
             }
             "HT" => {
                 check_min_num_fields!(fields, 5);
-                let aircraft_callsign = fields[3].to_uppercase();
-                let atc_callsign = fields[4].to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
+                let atc_callsign = parse_identifier(cx, fields, 4, "atc_callsign")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::AcceptHandoff {
                         aircraft_callsign,
                         atc_callsign,
@@ -1936,35 +2741,35 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
             }
             "DR" => {
                 check_min_num_fields!(fields, 4);
-                let aircraft_callsign = fields[3].to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::DropTrack { aircraft_callsign },
                 ))
             }
             "CAPS" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::Capabilities,
             )),
             "IT" => {
                 check_min_num_fields!(fields, 4);
-                let aircraft_callsign = fields[3].to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::InitiateTrack { aircraft_callsign },
                 ))
             }
             "HI" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::CancelRequestRelief,
             )),
             "INF" => Ok(ClientQueryMessage::new(
                 first,
-                fields[1],
+                parse_recipient(cx, fields, 1)?,
                 ClientQueryType::INF,
             )),
             "SIMTIME" => {
@@ -1975,52 +2780,76 @@ impl TryFrom<&[&str]> for ClientQueryMessage {
                         return Err(FsdMessageParseError::InvalidTime(format!(
                             "SIMTIME uses incorrect format: {}, {e}",
                             fields[3]
-                        )));
+                        ))
+                        .at_field(fields, 3, "time"));
                     }
                 };
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SimTime { time },
                 ))
             }
             "GD" => {
                 check_min_num_fields!(fields, 5);
-                let aircraft_callsign = fields[4].to_uppercase();
+                let aircraft_callsign = parse_identifier(cx, fields, 4, "aircraft_callsign")?;
                 let contents = fields[4].to_string();
                 Ok(ClientQueryMessage::new(
                     first,
-                    fields[1],
+                    parse_recipient(cx, fields, 1)?,
                     ClientQueryType::SetGlobalData {
                         aircraft_callsign,
                         contents,
                     },
                 ))
             }
-            _ => Err(FsdMessageParseError::UnknownMessageType(
-                fields[2].to_string(),
-            )),
+            "EST" => {
+                check_min_num_fields!(fields, 7);
+                let aircraft_callsign = parse_identifier(cx, fields, 3, "aircraft_callsign")?;
+                let time = NaiveTime::parse_from_str(fields[5], "%H%M").map_err(|_| {
+                    FsdMessageParseError::InvalidTime(fields[5].to_string())
+                        .at_field(fields, 5, "time")
+                })?;
+                let level = util::parse_level(cx, fields, 6, "level")?;
+                Ok(ClientQueryMessage::new(
+                    first,
+                    parse_recipient(cx, fields, 1)?,
+                    ClientQueryType::Estimate {
+                        aircraft_callsign,
+                        fix: fields[4].to_uppercase(),
+                        time,
+                        level,
+                    },
+                ))
+            }
+            _ => Err(
+                FsdMessageParseError::UnknownMessageType(fields[2].to_string()).at_field(
+                    fields,
+                    2,
+                    "query_type",
+                ),
+            ),
         }
     }
 }
 impl ClientQueryMessage {
-    fn new(from: impl AsRef<str>, to: impl AsRef<str>, query_type: ClientQueryType) -> Self {
+    fn new(from: Callsign, to: impl Into<Recipient>, query_type: ClientQueryType) -> Self {
         ClientQueryMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             query_type,
         }
     }
     pub fn force_beacon_code(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         code: TransponderCode,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::ForceBeaconCode { code })
     }
     pub fn help_request(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         message: Option<impl AsRef<str>>,
     ) -> ClientQueryMessage {
         let message = message.map(|msg| msg.as_ref().to_string());
@@ -2028,220 +2857,191 @@ impl ClientQueryMessage {
     }
 
     pub fn cancel_help_request(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         message: Option<impl AsRef<str>>,
     ) -> ClientQueryMessage {
         let message = message.map(|msg| msg.as_ref().to_string());
         ClientQueryMessage::new(from, to, ClientQueryType::CancelHelpRequest { message })
     }
 
-    pub fn com_1_freq(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn com_1_freq(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::Com1Freq)
     }
-    pub fn public_ip(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn public_ip(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::PublicIP)
     }
-    pub fn atis(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn atis(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::ATIS)
     }
-    pub fn real_name(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn real_name(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::RealName)
     }
-    pub fn server(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn server(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::Server)
     }
-    pub fn capabilities(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn capabilities(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::Capabilities)
     }
     pub fn is_valid_atc(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        atc_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        atc_callsign: Callsign,
     ) -> ClientQueryMessage {
-        ClientQueryMessage::new(
-            from,
-            to,
-            ClientQueryType::IsValidATC {
-                atc_callsign: atc_callsign.as_ref().to_uppercase(),
-            },
-        )
+        ClientQueryMessage::new(from, to, ClientQueryType::IsValidATC { atc_callsign })
     }
-    pub fn client_information(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn client_information(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::INF)
     }
     pub fn flight_plan(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> ClientQueryMessage {
-        ClientQueryMessage::new(
-            from,
-            to,
-            ClientQueryType::FlightPlan {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
-        )
+        ClientQueryMessage::new(from, to, ClientQueryType::FlightPlan { aircraft_callsign })
     }
-    pub fn request_relief(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn request_relief(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::RequestRelief)
     }
-    pub fn cancel_request_relief(from: impl AsRef<str>, to: impl AsRef<str>) -> ClientQueryMessage {
+    pub fn cancel_request_relief(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::CancelRequestRelief)
     }
     pub fn who_has(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> ClientQueryMessage {
-        ClientQueryMessage::new(
-            from,
-            to,
-            ClientQueryType::WhoHas {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
-        )
+        ClientQueryMessage::new(from, to, ClientQueryType::WhoHas { aircraft_callsign })
     }
     pub fn initiate_track(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
-            ClientQueryType::InitiateTrack {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
+            ClientQueryType::InitiateTrack { aircraft_callsign },
         )
     }
     pub fn accept_handoff(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
-        atc_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
+        atc_callsign: Callsign,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::AcceptHandoff {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-                atc_callsign: atc_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
+                atc_callsign,
             },
         )
     }
     pub fn drop_track(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> ClientQueryMessage {
-        ClientQueryMessage::new(
-            from,
-            to,
-            ClientQueryType::DropTrack {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
-        )
+        ClientQueryMessage::new(from, to, ClientQueryType::DropTrack { aircraft_callsign })
     }
     pub fn set_final_altitude(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         level: Level,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetFinalAltitude {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 level,
             },
         )
     }
     pub fn set_temp_altitude(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         level: Level,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetTempAltitude {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 level,
             },
         )
     }
     pub fn set_beacon_code(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         code: TransponderCode,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetBeaconCode {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 code,
             },
         )
     }
     pub fn set_scratchpad(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         contents: ScratchPad,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetScratchpad {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 contents,
             },
         )
     }
     pub fn set_global_data(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         contents: impl AsRef<str>,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetGlobalData {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 contents: contents.as_ref().to_string(),
             },
         )
     }
     pub fn set_voice_type(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         voice_capability: VoiceCapability,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
             from,
             to,
             ClientQueryType::SetVoiceType {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 voice_capability,
             },
         )
     }
-    pub fn aircraft_config_request(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-    ) -> ClientQueryMessage {
+    pub fn aircraft_config_request(from: Callsign, to: impl Into<Recipient>) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::AircraftConfigurationRequest)
     }
     pub fn aircraft_config_response(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         aircraft_config: AircraftConfig,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(
@@ -2251,15 +3051,15 @@ impl ClientQueryMessage {
         )
     }
     pub fn new_info(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         atis_letter: char,
     ) -> ClientQueryMessage {
         ClientQueryMessage::new(from, to, ClientQueryType::NewInfo { atis_letter })
     }
     pub fn new_atis(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         atis_letter: char,
         wind_dir_and_speed: impl AsRef<str>,
         pressure: impl AsRef<str>,
@@ -2274,13 +3074,53 @@ impl ClientQueryMessage {
             },
         )
     }
+    /// Tells another controller when `aircraft_callsign` is expected over `fix`, and at what level
+    ///
+    /// # Example
+    /// ```
+    /// use fsd_interface::{Callsign, Level, NaiveTime, messages::ClientQueryMessage};
+    ///
+    /// let estimate = ClientQueryMessage::estimate(
+    ///     Callsign::new("EGTT_CTR").unwrap(),
+    ///     Callsign::new("EGPX_CTR").unwrap(),
+    ///     Callsign::new("BAW1436").unwrap(),
+    ///     "girvo",
+    ///     NaiveTime::from_hms_opt(14, 35, 0).unwrap(),
+    ///     Level::FlightLevel(34000),
+    /// );
+    /// assert_eq!("$CQEGTT_CTR:EGPX_CTR:EST:BAW1436:GIRVO:1435:34000", estimate.to_string());
+    ///
+    /// // Fixes are uppercased when parsed too
+    /// let parsed = fsd_interface::parse_message("$CQEGTT_CTR:EGPX_CTR:EST:BAW1436:girvo:1435:34000").unwrap();
+    /// assert_eq!(fsd_interface::FsdMessageType::ClientQueryMessage(estimate), parsed);
+    /// ```
+    pub fn estimate(
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
+        fix: impl AsRef<str>,
+        time: NaiveTime,
+        level: Level,
+    ) -> ClientQueryMessage {
+        ClientQueryMessage::new(
+            from,
+            to,
+            ClientQueryType::Estimate {
+                aircraft_callsign,
+                fix: fix.as_ref().to_uppercase(),
+                time,
+                level,
+            },
+        )
+    }
 }
 
 #[non_exhaustive]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClientQueryResponseMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub response_type: ClientResponseType,
 }
 
@@ -2289,16 +3129,33 @@ impl Display for ClientQueryResponseMessage {
         write!(f, "$CR{}:{}:{}", self.from, self.to, self.response_type)
     }
 }
-impl TryFrom<&[&str]> for ClientQueryResponseMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for ClientQueryResponseMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 4);
 
-        let from = &fields[0][3..];
-        let to = fields[1];
+        let from = parse_sender(cx, fields, 3, "from")?;
+        let to = parse_recipient(cx, fields, 1)?;
+        // The most fields each response type has. ATIS text lines and capabilities have no maximum, as the text
+        // can contain colons and there can be any number of capabilities.
+        let max_fields = match (fields[2], fields[3]) {
+            ("C?" | "IP" | "SV", _) => 4,
+            ("ATIS", "T") => {
+                cx.check_trailing_colon(fields, 5)?;
+                fields.len()
+            }
+            ("CAPS", _) => {
+                cx.check_trailing_colon(fields, 4)?;
+                fields.len()
+            }
+            ("ATIS", _) | ("ATC", _) => 5,
+            ("RN", _) => 6,
+            _ => fields.len(),
+        };
+        cx.check_max_fields(fields, max_fields)?;
         let response_type = match fields[2] {
             "C?" => ClientResponseType::Com1Freq {
-                frequency: RadioFrequency::try_from_human_readable_string(fields[3])?,
+                frequency: RadioFrequency::try_from_human_readable_string(fields[3])
+                    .map_err(|e| e.at_field(fields, 3, "frequency"))?,
             },
             "ATIS" => {
                 check_min_num_fields!(fields, 5);
@@ -2318,32 +3175,58 @@ impl TryFrom<&[&str]> for ClientQueryResponseMessage {
                         } else {
                             fields[4]
                         };
+                        let logoff_time = match logoff_time.parse() {
+                            Ok(logoff_time) => Some(logoff_time),
+                            Err(_) if logoff_time.is_empty() => None,
+                            Err(_) => {
+                                cx.deviation(
+                                    ParseWarning::DefaultedField {
+                                        field: "logoff_time",
+                                        value: fields[4].to_string(),
+                                    },
+                                    FsdMessageParseError::InvalidTime(fields[4].to_string())
+                                        .at_field(fields, 4, "logoff_time"),
+                                )?;
+                                None
+                            }
+                        };
                         ClientResponseType::ATIS {
-                            atis_line: AtisLine::LogoffTime(logoff_time.parse().ok()),
+                            atis_line: AtisLine::LogoffTime(logoff_time),
                         }
                     }
                     "E" => {
-                        let line_count: usize = fields[4].parse().map_err(|_| {
-                            FsdMessageParseError::InvalidATISLine(fields[4].to_string())
-                        })?;
+                        let line_count: usize = parse_field_or(
+                            fields,
+                            4,
+                            "line_count",
+                            FsdMessageParseError::InvalidATISLine,
+                        )?;
                         ClientResponseType::ATIS {
                             atis_line: AtisLine::EndMarker(line_count),
                         }
-                    },
+                    }
                     "A" => {
-                        let letter = fields[4].to_uppercase().chars().next().filter(|letter| letter.is_ascii_uppercase());
-                        ClientResponseType::ATIS { atis_line: AtisLine::AtisLetter(letter) }
-                    },
-                    _ => return Err(FsdMessageParseError::InvalidATISLine(fields[3].to_string())),
+                        let letter = fields[4]
+                            .to_uppercase()
+                            .chars()
+                            .next()
+                            .filter(|letter| letter.is_ascii_uppercase());
+                        ClientResponseType::ATIS {
+                            atis_line: AtisLine::AtisLetter(letter),
+                        }
+                    }
+                    _ => {
+                        return Err(FsdMessageParseError::InvalidATISLine(fields[3].to_string())
+                            .at_field(fields, 3, "atis_line_type"));
+                    }
                 }
             }
             "RN" => {
-                check_min_num_fields!(fields, 4);
+                check_min_num_fields!(fields, 6);
                 let name = fields[3].to_string();
                 let sector_file: String = fields[4].to_string();
-                let rating: u8 = fields[5]
-                    .parse()
-                    .map_err(|_| FsdMessageParseError::InvalidRating(fields[5].to_string()))?;
+                let rating: u8 =
+                    parse_field_or(fields, 5, "rating", FsdMessageParseError::InvalidRating)?;
                 ClientResponseType::RealName {
                     name,
                     sector_file,
@@ -2351,16 +3234,10 @@ impl TryFrom<&[&str]> for ClientQueryResponseMessage {
                 }
             }
             "IP" => ClientResponseType::PublicIP {
-                ip_address: fields
-                    .get(3)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(4, fields.len()))?
-                    .to_string(),
+                ip_address: field(fields, 3, "ip_address")?.to_string(),
             },
             "SV" => ClientResponseType::Server {
-                hostname_or_ip_address: fields
-                    .get(3)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(4, fields.len()))?
-                    .to_string(),
+                hostname_or_ip_address: field(fields, 3, "hostname_or_ip_address")?.to_string(),
             },
             "ATC" => {
                 check_min_num_fields!(fields, 4);
@@ -2370,10 +3247,14 @@ impl TryFrom<&[&str]> for ClientQueryResponseMessage {
                     _ => {
                         return Err(FsdMessageParseError::InvalidValidAtcStatus(
                             fields[3].to_string(),
-                        ))
+                        )
+                        .at_field(fields, 3, "valid_atc"));
                     }
                 };
-                let atc_callsign = fields.get(4).unwrap_or(&fields[1]).to_uppercase();
+                let atc_callsign = match fields.get(4) {
+                    Some(_) => parse_identifier(cx, fields, 4, "atc_callsign")?,
+                    None => util::identifier(cx, fields[1], fields, 1, "atc_callsign")?,
+                };
                 ClientResponseType::IsValidATC {
                     atc_callsign,
                     valid_atc,
@@ -2385,40 +3266,44 @@ impl TryFrom<&[&str]> for ClientQueryResponseMessage {
                 ClientResponseType::Capabilities { capabilities }
             }
             _ => {
-                return Err(FsdMessageParseError::UnknownMessageType(
-                    fields[2].to_string(),
-                ))
+                return Err(
+                    FsdMessageParseError::UnknownMessageType(fields[2].to_string()).at_field(
+                        fields,
+                        2,
+                        "response_type",
+                    ),
+                );
             }
         };
         Ok(ClientQueryResponseMessage::new(from, to, response_type))
     }
 }
 impl ClientQueryResponseMessage {
-    fn new(from: impl AsRef<str>, to: impl AsRef<str>, response_type: ClientResponseType) -> Self {
+    fn new(from: Callsign, to: impl Into<Recipient>, response_type: ClientResponseType) -> Self {
         ClientQueryResponseMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             response_type,
         }
     }
 
     pub fn com_1_freq(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         frequency: RadioFrequency,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(from, to, ClientResponseType::Com1Freq { frequency })
     }
     pub fn atis(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         atis_line: AtisLine,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(from, to, ClientResponseType::ATIS { atis_line })
     }
     pub fn real_name(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         name: impl Into<String>,
         sector_file: impl Into<String>,
         rating: u8,
@@ -2434,8 +3319,8 @@ impl ClientQueryResponseMessage {
         )
     }
     pub fn capabilities(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         capabilities: impl Into<Vec<ClientCapability>>,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(
@@ -2447,8 +3332,8 @@ impl ClientQueryResponseMessage {
         )
     }
     pub fn public_ip(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         ip_address: impl Into<String>,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(
@@ -2460,8 +3345,8 @@ impl ClientQueryResponseMessage {
         )
     }
     pub fn server(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         hostname_or_ip_address: impl Into<String>,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(
@@ -2473,27 +3358,28 @@ impl ClientQueryResponseMessage {
         )
     }
     pub fn is_valid_atc(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        atc_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        atc_callsign: Callsign,
         valid: bool,
     ) -> ClientQueryResponseMessage {
         ClientQueryResponseMessage::new(
             from,
             to,
             ClientResponseType::IsValidATC {
-                atc_callsign: atc_callsign.as_ref().to_uppercase(),
+                atc_callsign,
                 valid_atc: valid,
             },
         )
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HandoffOfferMessage {
-    pub from: String,
-    pub to: String,
-    pub aircraft: String,
+    pub from: Callsign,
+    pub to: Recipient,
+    pub aircraft: Callsign,
 }
 
 impl Display for HandoffOfferMessage {
@@ -2502,32 +3388,34 @@ impl Display for HandoffOfferMessage {
     }
 }
 
-impl TryFrom<&[&str]> for HandoffOfferMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        if fields.len() < 3 {
-            return Err(FsdMessageParseError::InvalidFieldCount(3, fields.len()));
-        }
-        let first = &fields[0][3..];
-        Ok(HandoffOfferMessage::new(first, fields[1], fields[2]))
+impl ParseFields<'_> for HandoffOfferMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        Ok(HandoffOfferMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            parse_identifier(cx, fields, 2, "aircraft")?,
+        ))
     }
 }
 
 impl HandoffOfferMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, aircraft: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, aircraft: Callsign) -> Self {
         HandoffOfferMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
-            aircraft: aircraft.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
+            aircraft,
         }
     }
 }
 
 #[non_exhaustive]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SharedStateMessage {
-    pub from: String,
-    pub to: String,
+    pub from: Callsign,
+    pub to: Recipient,
     pub shared_state_type: SharedStateType,
 }
 
@@ -2540,338 +3428,335 @@ impl Display for SharedStateMessage {
         )
     }
 }
-impl TryFrom<&[&str]> for SharedStateMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+impl ParseFields<'_> for SharedStateMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
         check_min_num_fields!(fields, 4);
-        let from = &fields[0][3..];
-        let to = fields[1];
-        let shared_state_type = match fields[3] {
-            "VER" => SharedStateType::Version,
-            "ID" => SharedStateType::ID,
-            "DI" => SharedStateType::DI,
-            "IH" => SharedStateType::IHave {
-                aircraft_callsign: fields
-                    .get(4)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(5, fields.len()))?
-                    .to_uppercase(),
-            },
-            "SC" => {
-                check_min_num_fields!(fields, 6);
-                let scratchpad_contents = fields[5].parse()?;
-                SharedStateType::ScratchPad {
-                    aircraft_callsign: fields[4].to_uppercase(),
-                    contents: scratchpad_contents,
-                }
+        let from = parse_sender(cx, fields, 3, "from")?;
+        let to = parse_recipient(cx, fields, 1)?;
+        // The most fields each type has. Flight strips can hold any number of items.
+        let max_fields = match fields[3] {
+            "VER" | "ID" | "DI" | "IB" | "EC" | "OB" | "EO" | "MB" | "EM" => 4,
+            "IH" | "HC" | "PT" | "DP" => 5,
+            "SC" | "GD" | "TA" | "FA" | "VT" | "BC" | "IC" | "IK" | "OV" | "OK" | "MN" | "MK" => 6,
+            "ST" => {
+                cx.check_trailing_colon(fields, 5)?;
+                fields.len()
             }
-            "GD" => {
-                check_min_num_fields!(fields, 6);
-                let aircraft_callsign = fields[4].to_uppercase();
-                let contents = fields[5].to_string();
-                SharedStateType::GlobalData {
-                    aircraft_callsign,
-                    contents,
+            _ => fields.len(),
+        };
+        cx.check_max_fields(fields, max_fields)?;
+        let shared_state_type =
+            match fields[3] {
+                "VER" => SharedStateType::Version,
+                "ID" => SharedStateType::ID,
+                "DI" => SharedStateType::DI,
+                "IH" => SharedStateType::IHave {
+                    aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                },
+                "SC" => {
+                    check_min_num_fields!(fields, 6);
+                    let scratchpad_contents = parse_field(fields, 5, "contents")?;
+                    SharedStateType::ScratchPad {
+                        aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                        contents: scratchpad_contents,
+                    }
                 }
-            }
-            "TA" => {
-                check_min_num_fields!(fields, 6);
-                let level = fields[5].parse()?;
-                SharedStateType::TempAltitude {
-                    aircraft_callsign: fields[4].to_uppercase(),
-                    level,
+                "GD" => {
+                    check_min_num_fields!(fields, 6);
+                    let aircraft_callsign = parse_identifier(cx, fields, 4, "aircraft_callsign")?;
+                    let contents = fields[5].to_string();
+                    SharedStateType::GlobalData {
+                        aircraft_callsign,
+                        contents,
+                    }
                 }
-            }
-            "FA" => {
-                check_min_num_fields!(fields, 6);
-                let level = fields[5].parse()?;
-                SharedStateType::FinalAltitude {
-                    aircraft_callsign: fields[4].to_uppercase(),
-                    level,
+                "TA" => {
+                    check_min_num_fields!(fields, 6);
+                    let level = util::parse_level(cx, fields, 5, "level")?;
+                    SharedStateType::TempAltitude {
+                        aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                        level,
+                    }
                 }
-            }
-            "VT" => {
-                check_min_num_fields!(fields, 6);
-                let voice_capability: VoiceCapability = fields[5].into();
-                SharedStateType::VoiceType {
-                    aircraft_callsign: fields[4].to_uppercase(),
-                    voice_capability,
+                "FA" => {
+                    check_min_num_fields!(fields, 6);
+                    let level = util::parse_level(cx, fields, 5, "level")?;
+                    SharedStateType::FinalAltitude {
+                        aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                        level,
+                    }
                 }
-            }
-            "BC" => {
-                check_min_num_fields!(fields, 4);
-                let code: TransponderCode = fields[5].parse()?;
-                SharedStateType::BeaconCode {
-                    aircraft_callsign: fields[4].to_uppercase(),
-                    code,
+                "VT" => {
+                    check_min_num_fields!(fields, 6);
+                    let voice_capability = match fields[5].parse::<VoiceCapability>() {
+                        Ok(voice_capability) => voice_capability,
+                        Err(e) => {
+                            cx.deviation(
+                                ParseWarning::DefaultedField {
+                                    field: "voice_capability",
+                                    value: fields[5].to_string(),
+                                },
+                                e.at_field(fields, 5, "voice_capability"),
+                            )?;
+                            VoiceCapability::default()
+                        }
+                    };
+                    SharedStateType::VoiceType {
+                        aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                        voice_capability,
+                    }
                 }
-            }
-            "HC" => SharedStateType::HandoffCancel {
-                aircraft_callsign: fields
-                    .get(4)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(5, fields.len()))?
-                    .to_uppercase(),
-            },
-            "PT" => SharedStateType::PointOut {
-                aircraft_callsign: fields
-                    .get(4)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(5, fields.len()))?
-                    .to_uppercase(),
-            },
-            "DP" => SharedStateType::PushToDepartureList {
-                aircraft_callsign: fields
-                    .get(4)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(5, fields.len()))?
-                    .to_uppercase(),
-            },
-            "ST" => {
-                let aircraft_callsign = fields
-                    .get(4)
-                    .ok_or(FsdMessageParseError::InvalidFieldCount(5, fields.len()))?
-                    .to_uppercase();
-                let format = fields.get(5).and_then(|i| i.parse::<i32>().ok());
-                let contents = fields
-                    .get(6..)
-                    .map(|c| c.iter().map(|e| e.to_string()).collect::<Vec<_>>());
-                SharedStateType::FlightStrip {
-                    aircraft_callsign,
-                    format,
-                    contents,
+                "BC" => {
+                    check_min_num_fields!(fields, 4);
+                    let code: TransponderCode = parse_field(fields, 5, "code")?;
+                    SharedStateType::BeaconCode {
+                        aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                        code,
+                    }
                 }
-            }
-            "IC" | "IK" | "IB" | "EC" | "OV" | "OK" | "OB" | "EO" | "MN" | "MK" | "MB" | "EM" => {
-                let ip_address = fields
-                    .get(4)
-                    .and_then(|ip| ip.parse::<Ipv4Addr>().ok())
-                    .ok_or_else(|| {
-                        FsdMessageParseError::InvalidIPAddress(
-                            fields.get(4).map(|x| x.to_string()).unwrap_or_default(),
-                        )
-                    });
-                let port = fields
-                    .get(5)
-                    .and_then(|port| port.parse::<u16>().ok())
-                    .ok_or_else(|| {
-                        FsdMessageParseError::InvalidPort(
-                            fields.get(5).map(|x| x.to_string()).unwrap_or_default(),
-                        )
-                    });
-                let (landline_type, landline_command) = match fields[3] {
-                    "IC" => (
-                        LandLineType::Intercom,
-                        LandLineCommand::Request {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "IK" => (
-                        LandLineType::Intercom,
-                        LandLineCommand::Approve {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "IB" => (LandLineType::Intercom, LandLineCommand::Reject),
-                    "EC" => (LandLineType::Intercom, LandLineCommand::End),
-
-                    "OV" => (
-                        LandLineType::Override,
-                        LandLineCommand::Request {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "OK" => (
-                        LandLineType::Override,
-                        LandLineCommand::Approve {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "OB" => (LandLineType::Override, LandLineCommand::Reject),
-                    "EO" => (LandLineType::Override, LandLineCommand::End),
-
-                    "MN" => (
-                        LandLineType::Monitor,
-                        LandLineCommand::Request {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "MK" => (
-                        LandLineType::Monitor,
-                        LandLineCommand::Approve {
-                            ip_address: ip_address?,
-                            port: port?,
-                        },
-                    ),
-                    "MB" => (LandLineType::Monitor, LandLineCommand::Reject),
-                    "EM" => (LandLineType::Monitor, LandLineCommand::End),
-
-                    _ => unreachable!(),
-                };
-                SharedStateType::LandLine {
-                    landline_type,
-                    landline_command,
+                "HC" => SharedStateType::HandoffCancel {
+                    aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                },
+                "PT" => SharedStateType::PointOut {
+                    aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                },
+                "DP" => SharedStateType::PushToDepartureList {
+                    aircraft_callsign: parse_identifier(cx, fields, 4, "aircraft_callsign")?,
+                },
+                "ST" => {
+                    let aircraft_callsign = parse_identifier(cx, fields, 4, "aircraft_callsign")?;
+                    let format = fields.get(5).and_then(|i| i.parse::<i32>().ok());
+                    let contents = fields
+                        .get(6..)
+                        .map(|c| c.iter().map(|e| e.to_string()).collect::<Vec<_>>());
+                    SharedStateType::FlightStrip {
+                        aircraft_callsign,
+                        format,
+                        contents,
+                    }
                 }
-            }
-            _ => {
-                return Err(FsdMessageParseError::InvalidSharedStateType(
-                    fields[3].to_string(),
-                ))
-            }
-        };
+                "IC" | "IK" | "IB" | "EC" | "OV" | "OK" | "OB" | "EO" | "MN" | "MK" | "MB"
+                | "EM" => {
+                    let ip_address = fields
+                        .get(4)
+                        .and_then(|ip| ip.parse::<Ipv4Addr>().ok())
+                        .ok_or_else(|| {
+                            FsdMessageParseError::InvalidIPAddress(
+                                fields.get(4).map(|x| x.to_string()).unwrap_or_default(),
+                            )
+                            .at_field(fields, 4, "ip_address")
+                        });
+                    let port = fields
+                        .get(5)
+                        .and_then(|port| port.parse::<u16>().ok())
+                        .ok_or_else(|| {
+                            FsdMessageParseError::InvalidPort(
+                                fields.get(5).map(|x| x.to_string()).unwrap_or_default(),
+                            )
+                            .at_field(fields, 5, "port")
+                        });
+                    let (landline_type, landline_command) = match fields[3] {
+                        "IC" => (
+                            LandLineType::Intercom,
+                            LandLineCommand::Request {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "IK" => (
+                            LandLineType::Intercom,
+                            LandLineCommand::Approve {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "IB" => (LandLineType::Intercom, LandLineCommand::Reject),
+                        "EC" => (LandLineType::Intercom, LandLineCommand::End),
+
+                        "OV" => (
+                            LandLineType::Override,
+                            LandLineCommand::Request {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "OK" => (
+                            LandLineType::Override,
+                            LandLineCommand::Approve {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "OB" => (LandLineType::Override, LandLineCommand::Reject),
+                        "EO" => (LandLineType::Override, LandLineCommand::End),
+
+                        "MN" => (
+                            LandLineType::Monitor,
+                            LandLineCommand::Request {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "MK" => (
+                            LandLineType::Monitor,
+                            LandLineCommand::Approve {
+                                ip_address: ip_address?,
+                                port: port?,
+                            },
+                        ),
+                        "MB" => (LandLineType::Monitor, LandLineCommand::Reject),
+                        "EM" => (LandLineType::Monitor, LandLineCommand::End),
+
+                        _ => unreachable!(),
+                    };
+                    SharedStateType::LandLine {
+                        landline_type,
+                        landline_command,
+                    }
+                }
+                _ => {
+                    return Err(FsdMessageParseError::InvalidSharedStateType(
+                        fields[3].to_string(),
+                    )
+                    .at_field(fields, 3, "shared_state_type"));
+                }
+            };
 
         Ok(SharedStateMessage::new(from, to, shared_state_type))
     }
 }
 impl SharedStateMessage {
-    fn new(from: impl AsRef<str>, to: impl AsRef<str>, shared_state_type: SharedStateType) -> Self {
+    fn new(from: Callsign, to: impl Into<Recipient>, shared_state_type: SharedStateType) -> Self {
         SharedStateMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
             shared_state_type,
         }
     }
-    pub fn version(from: impl AsRef<str>, to: impl AsRef<str>) -> SharedStateMessage {
+    pub fn version(from: Callsign, to: impl Into<Recipient>) -> SharedStateMessage {
         SharedStateMessage::new(from, to, SharedStateType::Version)
     }
-    pub fn id(from: impl AsRef<str>, to: impl AsRef<str>) -> SharedStateMessage {
+    pub fn id(from: Callsign, to: impl Into<Recipient>) -> SharedStateMessage {
         SharedStateMessage::new(from, to, SharedStateType::ID)
     }
-    pub fn di(from: impl AsRef<str>, to: impl AsRef<str>) -> SharedStateMessage {
+    pub fn di(from: Callsign, to: impl Into<Recipient>) -> SharedStateMessage {
         SharedStateMessage::new(from, to, SharedStateType::DI)
     }
     pub fn i_have(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> SharedStateMessage {
-        SharedStateMessage::new(
-            from,
-            to,
-            SharedStateType::IHave {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
-        )
+        SharedStateMessage::new(from, to, SharedStateType::IHave { aircraft_callsign })
     }
     pub fn scratchpad(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         contents: ScratchPad,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
             SharedStateType::ScratchPad {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 contents,
             },
         )
     }
     pub fn global_data(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         contents: impl AsRef<str>,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
             SharedStateType::GlobalData {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 contents: contents.as_ref().to_string(),
             },
         )
     }
     pub fn temp_altitude(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         level: Level,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
             SharedStateType::TempAltitude {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 level,
             },
         )
     }
     pub fn beacon_code(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         code: TransponderCode,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
             SharedStateType::BeaconCode {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 code,
             },
         )
     }
     pub fn voice_type(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         voice_capability: VoiceCapability,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
             SharedStateType::VoiceType {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 voice_capability,
             },
         )
     }
     pub fn handoff_cancel(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
-            SharedStateType::HandoffCancel {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
+            SharedStateType::HandoffCancel { aircraft_callsign },
         )
     }
     pub fn point_out(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> SharedStateMessage {
-        SharedStateMessage::new(
-            from,
-            to,
-            SharedStateType::PointOut {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
-        )
+        SharedStateMessage::new(from, to, SharedStateType::PointOut { aircraft_callsign })
     }
     pub fn push_to_departure_list(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
     ) -> SharedStateMessage {
         SharedStateMessage::new(
             from,
             to,
-            SharedStateType::PushToDepartureList {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
-            },
+            SharedStateType::PushToDepartureList { aircraft_callsign },
         )
     }
     pub fn flight_strip(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
-        aircraft_callsign: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
+        aircraft_callsign: Callsign,
         format: Option<i32>,
         contents: Option<Vec<String>>,
     ) -> SharedStateMessage {
@@ -2879,15 +3764,15 @@ impl SharedStateMessage {
             from,
             to,
             SharedStateType::FlightStrip {
-                aircraft_callsign: aircraft_callsign.as_ref().to_uppercase(),
+                aircraft_callsign,
                 format,
                 contents,
             },
         )
     }
     pub fn land_line(
-        from: impl AsRef<str>,
-        to: impl AsRef<str>,
+        from: Callsign,
+        to: impl Into<Recipient>,
         landline_type: LandLineType,
         landline_command: LandLineCommand,
     ) -> SharedStateMessage {
@@ -2902,11 +3787,12 @@ impl SharedStateMessage {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HandoffAcceptMessage {
-    pub from: String,
-    pub to: String,
-    pub aircraft: String,
+    pub from: Callsign,
+    pub to: Recipient,
+    pub aircraft: Callsign,
 }
 
 impl Display for HandoffAcceptMessage {
@@ -2915,23 +3801,24 @@ impl Display for HandoffAcceptMessage {
     }
 }
 
-impl TryFrom<&[&str]> for HandoffAcceptMessage {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        if fields.len() < 3 {
-            return Err(FsdMessageParseError::InvalidFieldCount(3, fields.len()));
-        }
-        let first = &fields[0][3..];
-        Ok(HandoffAcceptMessage::new(first, fields[1], fields[2]))
+impl ParseFields<'_> for HandoffAcceptMessage {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_num_fields!(cx, fields, 3..=3);
+        let first = parse_sender(cx, fields, 3, "from")?;
+        Ok(HandoffAcceptMessage::new(
+            first,
+            parse_recipient(cx, fields, 1)?,
+            parse_identifier(cx, fields, 2, "aircraft")?,
+        ))
     }
 }
 
 impl HandoffAcceptMessage {
-    pub fn new(from: impl AsRef<str>, to: impl AsRef<str>, aircraft: impl AsRef<str>) -> Self {
+    pub fn new(from: Callsign, to: impl Into<Recipient>, aircraft: Callsign) -> Self {
         HandoffAcceptMessage {
-            from: from.as_ref().to_uppercase(),
-            to: to.as_ref().to_uppercase(),
-            aircraft: aircraft.as_ref().to_uppercase(),
+            from,
+            to: to.into(),
+            aircraft,
         }
     }
 }
@@ -0,0 +1,241 @@
+//! Options controlling how strictly messages are parsed
+//!
+//! FSD clients in the wild don't always follow the protocol exactly. Some send trailing colons
+//! or extra fields, and some leave out values that the protocol says are required.
+//! [`ParseMode::Lenient`] accepts these messages and records what was wrong with them as
+//! [`ParseWarning`]s, while [`ParseMode::Strict`] rejects them.
+//!
+//! Without options, as in [`parse_message`][crate::parse_message] and the `TryFrom` implementations of the
+//! message types, deviations are accepted without being recorded. Extra fields on a message of a fixed length,
+//! such as a flight plan, are the exception and are always rejected.
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{ParseOptions, parse_message, parse_message_with};
+//!
+//! // A flight plan with a trailing colon
+//! let line = "$FPBAW123:*A:I:B738:450:EGLL:1200:1200:FL350:LEMD:2:10:3:30:LEPA:/V/:DCT:";
+//!
+//! let parsed = parse_message_with(line, &ParseOptions::lenient()).unwrap();
+//! assert_eq!(1, parsed.warnings.len());
+//!
+//! assert!(parse_message_with(line, &ParseOptions::strict()).is_err());
+//! assert!(parse_message(line).is_err());
+//!
+//! // A ping with a trailing colon
+//! let line = "$PISERVER:BAW123:8415:";
+//! assert!(parse_message(line).is_ok());
+//! assert!(parse_message_with(line, &ParseOptions::strict()).is_err());
+//! ```
+
+use std::fmt::Display;
+
+use crate::{Dialect, FsdMessageType, errors::FsdMessageParseError};
+
+/// How to treat messages that deviate from the protocol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseMode {
+    /// Reject any message that deviates from the protocol
+    Strict,
+    /// Accept deviations where the message can still be understood, and record a [`ParseWarning`] for each
+    #[default]
+    Lenient,
+}
+
+/// Options passed to [`parse_message_with`][crate::parse_message_with]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    /// The dialect of the protocol that messages are in
+    pub dialect: Dialect,
+}
+
+impl ParseOptions {
+    /// Options that reject any deviation from the protocol
+    pub fn strict() -> Self {
+        ParseOptions {
+            mode: ParseMode::Strict,
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// Options that accept deviations from the protocol and record them as warnings. This is the default.
+    pub fn lenient() -> Self {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+            dialect: Dialect::default(),
+        }
+    }
+}
+
+/// A deviation from the protocol that was accepted in [`ParseMode::Lenient`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseWarning {
+    /// The message had more fields than expected. Extra fields were ignored, or kept as part of free text such as
+    /// the body of a text message.
+    ExtraFields { expected: usize, found: usize },
+    /// A field was missing or invalid, and a default value was used instead
+    DefaultedField { field: &'static str, value: String },
+    /// An invalid entry in a list was skipped
+    SkippedValue { field: &'static str, value: String },
+    /// The message type was not recognised, so it was kept as [`FsdMessageType::Unknown`]
+    UnknownMessage,
+    /// The recipient was not valid, so it was kept as a [`Recipient::Callsign`][crate::Recipient::Callsign]
+    InvalidRecipient { value: String },
+    /// A callsign or CID broke the naming rules, but was kept as it could still be sent back
+    InvalidIdentifier { field: &'static str, value: String },
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::ExtraFields { expected, found } => {
+                write!(f, "expected at most {expected} fields but found {found}")
+            }
+            ParseWarning::DefaultedField { field, value } => {
+                write!(f, "{field} had invalid value \"{value}\", default used")
+            }
+            ParseWarning::SkippedValue { field, value } => {
+                write!(f, "invalid value \"{value}\" in {field} was skipped")
+            }
+            ParseWarning::UnknownMessage => write!(f, "unknown message type"),
+            ParseWarning::InvalidRecipient { value } => {
+                write!(f, "invalid recipient \"{value}\" was kept as a callsign")
+            }
+            ParseWarning::InvalidIdentifier { field, value } => {
+                write!(f, "{field} had invalid value \"{value}\", kept as is")
+            }
+        }
+    }
+}
+
+/// A parsed message, along with any deviations from the protocol that were accepted
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMessage {
+    pub message: FsdMessageType,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// A type that can be parsed from the fields of a message, following the options in a [`ParseContext`]
+pub(crate) trait ParseFields<'a>: Sized {
+    fn parse_fields(
+        fields: &[&'a str],
+        cx: &mut ParseContext,
+    ) -> Result<Self, FsdMessageParseError>;
+}
+
+/// The state of a single parse: the options that apply, and the deviations accepted so far
+///
+/// The default context is the one used by [`parse_message`][crate::parse_message] and the `TryFrom`
+/// implementations. It accepts deviations without recording them, except for extra fields on a message that
+/// has a fixed length, which it rejects.
+#[derive(Debug, Default)]
+pub(crate) struct ParseContext {
+    mode: ParseMode,
+    dialect: Dialect,
+    /// Whether deviations are recorded as warnings, which is only done when options were given
+    recording: bool,
+    warnings: Vec<ParseWarning>,
+}
+
+impl ParseContext {
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        ParseContext {
+            mode: options.mode,
+            dialect: options.dialect,
+            recording: true,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Parses `fields` as a `T` in this context
+    #[inline]
+    pub(crate) fn parse<'a, T: ParseFields<'a>>(
+        &mut self,
+        fields: &[&'a str],
+    ) -> Result<T, FsdMessageParseError> {
+        T::parse_fields(fields, self)
+    }
+
+    /// The dialect of the message being parsed
+    pub(crate) fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// The deviations accepted so far
+    pub(crate) fn into_warnings(self) -> Vec<ParseWarning> {
+        self.warnings
+    }
+
+    /// Records a deviation from the protocol. Returns `error` in strict mode, otherwise records `warning`.
+    pub(crate) fn deviation(
+        &mut self,
+        warning: ParseWarning,
+        error: FsdMessageParseError,
+    ) -> Result<(), FsdMessageParseError> {
+        if self.mode == ParseMode::Strict {
+            return Err(error);
+        }
+        if self.recording {
+            self.warnings.push(warning);
+        }
+        Ok(())
+    }
+
+    /// Records a deviation if there are more than the `max` fields the message reads
+    pub(crate) fn check_max_fields(
+        &mut self,
+        fields: &[&str],
+        max: usize,
+    ) -> Result<(), FsdMessageParseError> {
+        if fields.len() <= max {
+            return Ok(());
+        }
+        self.deviation(
+            ParseWarning::ExtraFields {
+                expected: max,
+                found: fields.len(),
+            },
+            FsdMessageParseError::InvalidFieldCount(max, fields.len()).in_line(fields),
+        )
+    }
+
+    /// Like [`check_max_fields`][ParseContext::check_max_fields], for messages that have a fixed length. These have
+    /// always rejected extra fields, so only accept them when lenient options were given.
+    pub(crate) fn check_fixed_fields(
+        &mut self,
+        fields: &[&str],
+        len: usize,
+    ) -> Result<(), FsdMessageParseError> {
+        if fields.len() > len && !self.recording {
+            return Err(FsdMessageParseError::InvalidFieldCount(len, fields.len()).in_line(fields));
+        }
+        self.check_max_fields(fields, len)
+    }
+
+    /// Records a deviation if a message that ends in free text, which may contain colons, has a trailing colon.
+    /// The colon is kept as part of the text if the deviation is accepted.
+    pub(crate) fn check_trailing_colon(
+        &mut self,
+        fields: &[&str],
+        min: usize,
+    ) -> Result<(), FsdMessageParseError> {
+        let Some(last) = fields.last() else {
+            return Ok(());
+        };
+        // Text messages are only split as far as the text, so the colon can be inside the last field
+        if !last.ends_with(':') && (fields.len() <= min || !last.is_empty()) {
+            return Ok(());
+        }
+        let found = fields.len() + last.matches(':').count();
+        self.deviation(
+            ParseWarning::ExtraFields {
+                expected: found - 1,
+                found,
+            },
+            FsdMessageParseError::InvalidFieldCount(found - 1, found).in_line(fields),
+        )
+    }
+}
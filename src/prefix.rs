@@ -0,0 +1,199 @@
+//! Identifies the type of a message from the first one to three bytes of the line
+
+/// The prefix at the start of every known message type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Prefix {
+    AtcRegister,
+    PilotRegister,
+    AtcDeregister,
+    PilotDeregister,
+    Text,
+    VelocityStopped,
+    VelocitySlow,
+    Heartbeat,
+    SharedState,
+    SquawkBox,
+    WeatherRequest,
+    TemperatureData,
+    WindData,
+    CloudData,
+    AuthenticationChallenge,
+    AuthenticationResponse,
+    Error,
+    HandoffOffer,
+    HandoffAccept,
+    ChangeServer,
+    FlightPlan,
+    FlightPlanAmendment,
+    ServerHandshake,
+    ClientHandshake,
+    SendFastPositions,
+    Kill,
+    MetarRequest,
+    MetarResponse,
+    ClientQuery,
+    ClientQueryResponse,
+    Ping,
+    Pong,
+    AtcPosition,
+    SecondaryVisCentre,
+    PilotPosition,
+    VelocityFast,
+}
+
+impl Prefix {
+    /// Returns the prefix at the start of `line`, if it is a known one.
+    ///
+    /// This is a single match on the first one to three bytes, so it takes the same time whatever the prefix.
+    pub(crate) fn of(line: &str) -> Option<Prefix> {
+        use Prefix::*;
+        let prefix = match line.as_bytes() {
+            [b'%', ..] => AtcPosition,
+            [b'\'', ..] => SecondaryVisCentre,
+            [b'@', ..] => PilotPosition,
+            [b'^', ..] => VelocityFast,
+            [b'#', a, b, ..] => match [*a, *b] {
+                [b'A', b'A'] => AtcRegister,
+                [b'A', b'P'] => PilotRegister,
+                [b'D', b'A'] => AtcDeregister,
+                [b'D', b'P'] => PilotDeregister,
+                [b'T', b'M'] => Text,
+                [b'S', b'T'] => VelocityStopped,
+                [b'S', b'L'] => VelocitySlow,
+                [b'D', b'L'] => Heartbeat,
+                [b'P', b'C'] => SharedState,
+                [b'S', b'B'] => SquawkBox,
+                [b'R', b'W'] => WeatherRequest,
+                [b'T', b'D'] => TemperatureData,
+                [b'W', b'D'] => WindData,
+                [b'C', b'D'] => CloudData,
+                _ => return None,
+            },
+            [b'$', a, b, ..] => match [*a, *b] {
+                [b'Z', b'C'] => AuthenticationChallenge,
+                [b'Z', b'R'] => AuthenticationResponse,
+                [b'E', b'R'] => Error,
+                [b'H', b'O'] => HandoffOffer,
+                [b'H', b'A'] => HandoffAccept,
+                [b'X', b'X'] => ChangeServer,
+                [b'F', b'P'] => FlightPlan,
+                [b'A', b'M'] => FlightPlanAmendment,
+                [b'D', b'I'] => ServerHandshake,
+                [b'I', b'D'] => ClientHandshake,
+                [b'S', b'F'] => SendFastPositions,
+                [b'!', b'!'] => Kill,
+                [b'A', b'X'] => MetarRequest,
+                [b'A', b'R'] => MetarResponse,
+                [b'C', b'Q'] => ClientQuery,
+                [b'C', b'R'] => ClientQueryResponse,
+                [b'P', b'I'] => Ping,
+                [b'P', b'O'] => Pong,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(prefix)
+    }
+
+    /// The prefix as it appears on the wire
+    pub(crate) fn as_str(self) -> &'static str {
+        use Prefix::*;
+        match self {
+            AtcRegister => "#AA",
+            PilotRegister => "#AP",
+            AtcDeregister => "#DA",
+            PilotDeregister => "#DP",
+            Text => "#TM",
+            VelocityStopped => "#ST",
+            VelocitySlow => "#SL",
+            Heartbeat => "#DL",
+            SharedState => "#PC",
+            SquawkBox => "#SB",
+            WeatherRequest => "#RW",
+            TemperatureData => "#TD",
+            WindData => "#WD",
+            CloudData => "#CD",
+            AuthenticationChallenge => "$ZC",
+            AuthenticationResponse => "$ZR",
+            Error => "$ER",
+            HandoffOffer => "$HO",
+            HandoffAccept => "$HA",
+            ChangeServer => "$XX",
+            FlightPlan => "$FP",
+            FlightPlanAmendment => "$AM",
+            ServerHandshake => "$DI",
+            ClientHandshake => "$ID",
+            SendFastPositions => "$SF",
+            Kill => "$!!",
+            MetarRequest => "$AX",
+            MetarResponse => "$AR",
+            ClientQuery => "$CQ",
+            ClientQueryResponse => "$CR",
+            Ping => "$PI",
+            Pong => "$PO",
+            AtcPosition => "%",
+            SecondaryVisCentre => "'",
+            PilotPosition => "@",
+            VelocityFast => "^",
+        }
+    }
+
+    /// Splits `line` into its colon-delimited fields.
+    ///
+    /// Text messages keep any colons in the text itself, so only their first three fields are split off.
+    /// Position updates, which make up most traffic, are split onto the stack. Every other type is split in full
+    /// into a `Vec` before it is parsed.
+    pub(crate) fn split(self, line: &str) -> Fields<'_> {
+        match self {
+            Prefix::Text => Fields::Heap(line.splitn(3, ':').collect()),
+            Prefix::PilotPosition
+            | Prefix::VelocityFast
+            | Prefix::VelocitySlow
+            | Prefix::VelocityStopped => split_inline(line),
+            _ => Fields::Heap(split_fields(line)),
+        }
+    }
+}
+
+/// How many fields a line can have and still be split onto the stack. Position updates have at most 13.
+const MAX_INLINE_FIELDS: usize = 16;
+
+/// The fields of a line, split onto the stack when there are few enough of them
+// Boxing the array would bring back the allocation it is there to avoid
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Fields<'a> {
+    Inline([&'a str; MAX_INLINE_FIELDS], usize),
+    Heap(Vec<&'a str>),
+}
+
+impl<'a> std::ops::Deref for Fields<'a> {
+    type Target = [&'a str];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Fields::Inline(fields, len) => &fields[..*len],
+            Fields::Heap(fields) => fields,
+        }
+    }
+}
+
+/// Splits `line` on every colon onto the stack, falling back to a `Vec` if it has too many fields
+fn split_inline(line: &str) -> Fields<'_> {
+    let mut fields = [""; MAX_INLINE_FIELDS];
+    let mut len = 0;
+    for field in line.split(':') {
+        let Some(slot) = fields.get_mut(len) else {
+            return Fields::Heap(split_fields(line));
+        };
+        *slot = field;
+        len += 1;
+    }
+    Fields::Inline(fields, len)
+}
+
+/// Splits `line` on every colon, allocating space for all of the fields up front
+pub(crate) fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::with_capacity(line.bytes().filter(|&b| b == b':').count() + 1);
+    fields.extend(line.split(':'));
+    fields
+}
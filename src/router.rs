@@ -0,0 +1,284 @@
+//! Works out which clients should receive a message, following FSD's visibility rules
+//!
+//! [`Router`] keeps the last known position and visibility range of every client it is told about, and resolves
+//! the recipients of each message from them:
+//! - pilot position updates (`@`, `#ST`, `#SL` and `^`) reach the clients whose visibility covers the aircraft
+//! - broadcasts (`*`, `*A` and `*P`), frequency messages (`@xxxxx`), controller position updates and anything
+//!   else unaddressed reach the clients in range of the sender, either because the sender can see them or
+//!   because they can see the sender. Clients are expected to filter frequency messages by what they are tuned
+//!   to, which the server doesn't know.
+//! - messages to a callsign reach that client wherever it is, as do messages to supervisors (`*S`)
+//!
+//! A controller sees [`AtcPositionUpdateMessage::vis_range`] nautical miles around its position and each of its
+//! secondary visibility centres, up to the [`AtcType::max_range`][crate::AtcType::max_range] of its facility.
+//! A pilot sees further the higher they fly, `10 + 1.414 * sqrt(altitude)` nautical miles as in the original FSD
+//! server. Clients that haven't sent a position yet are out of range of everyone.
+//!
+//! # Example
+//! ```
+//! use fsd_interface::{Callsign, Router, parse_message};
+//!
+//! let mut router = Router::new();
+//! for line in [
+//!     "#AAEGLL_TWR:SERVER:John Smith:7654321::5:9",
+//!     "%EGLL_TWR:18500:4:50:5:51.47750:-0.46139:0",
+//!     "#APBAW123:SERVER:1234567::1:9:1:Jane Doe",
+//!     "@N:BAW123:2000:1:51.46000:-0.30000:1500:0:0:0",
+//!     "#APAFR456:SERVER:2345678::1:9:1:Jean Dupont",
+//!     "@N:AFR456:2000:1:49.00970:2.54780:0:0:0:0",
+//! ] {
+//!     router.update(&parse_message(line).unwrap());
+//! }
+//!
+//! // Heathrow tower can see the aircraft approaching it, but not the one at Charles de Gaulle
+//! let position = parse_message("@N:BAW123:2000:1:51.46500:-0.35000:1200:0:0:0").unwrap();
+//! assert_eq!(vec![Callsign::new("EGLL_TWR").unwrap()], router.recipients(&position));
+//! let position = parse_message("@N:AFR456:2000:1:49.00970:2.54780:0:0:0:0").unwrap();
+//! assert!(router.recipients(&position).is_empty());
+//!
+//! // A secondary visibility centre over Paris brings it into view
+//! router.update(&parse_message("'EGLL_TWR:0:49.00000:2.50000").unwrap());
+//! assert_eq!(vec![Callsign::new("EGLL_TWR").unwrap()], router.recipients(&position));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    AtcRating, Callsign, FsdMessage, FsdMessageType, Recipient, messages::AtcPositionUpdateMessage,
+    session::Role,
+};
+
+/// How many secondary visibility centres a controller can have
+pub const MAX_SECONDARY_VIS_CENTRES: usize = 4;
+
+/// The mean radius of the Earth, in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// The last known visibility of a client
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visibility {
+    pub role: Role,
+    /// Latitude and longitude, in degrees
+    pub position: Option<(f64, f64)>,
+    /// How far the client can see from its position and secondary centres, in nautical miles
+    pub range: f64,
+    /// Extra points a controller can see around, as set by `'` messages
+    pub secondary_centres: [Option<(f64, f64)>; MAX_SECONDARY_VIS_CENTRES],
+}
+
+impl Visibility {
+    fn new(role: Role) -> Self {
+        Visibility {
+            role,
+            position: None,
+            range: 0.0,
+            secondary_centres: [None; MAX_SECONDARY_VIS_CENTRES],
+        }
+    }
+
+    /// Whether `point` is within range of the client's position or any of its secondary centres
+    pub fn covers(&self, point: (f64, f64)) -> bool {
+        self.position
+            .iter()
+            .chain(self.secondary_centres.iter().flatten())
+            .any(|centre| distance_nm(*centre, point) <= self.range)
+    }
+}
+
+/// Resolves the recipients of FSD messages from the positions and ranges of the connected clients
+#[derive(Debug, Clone, Default)]
+pub struct Router {
+    clients: HashMap<Callsign, Visibility>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    /// Starts tracking a client. Its position is unknown until it sends one.
+    pub fn add_client(&mut self, callsign: Callsign, role: Role) {
+        self.clients.insert(callsign, Visibility::new(role));
+    }
+
+    pub fn remove_client(&mut self, callsign: Callsign) {
+        self.clients.remove(&callsign);
+    }
+
+    /// The last known visibility of a client, if it is being tracked
+    pub fn visibility(&self, callsign: Callsign) -> Option<&Visibility> {
+        self.clients.get(&callsign)
+    }
+
+    /// Learns from a message: registrations and deregistrations add and remove clients, and position updates
+    /// move them. Anything else is ignored.
+    pub fn update(&mut self, message: &FsdMessageType) {
+        match message {
+            FsdMessageType::PilotRegisterMessage(registration) => self.add_client(
+                registration.from,
+                Role::Pilot {
+                    rating: registration.rating,
+                    simulator_type: registration.simulator_type,
+                },
+            ),
+            FsdMessageType::AtcRegisterMessage(registration) => self.add_client(
+                registration.from,
+                Role::Atc {
+                    rating: registration.rating,
+                },
+            ),
+            FsdMessageType::PilotDeregisterMessage(deregistration) => {
+                self.remove_client(deregistration.from)
+            }
+            FsdMessageType::AtcDeregisterMessage(deregistration) => {
+                self.remove_client(deregistration.from)
+            }
+            FsdMessageType::AtcPositionUpdateMessage(update) => {
+                if let Some(client) = self.clients.get_mut(&update.callsign) {
+                    client.position = Some((update.latitude, update.longitude));
+                    client.range = atc_range(update);
+                }
+            }
+            FsdMessageType::AtcSecondaryVisCentreMessage(centre) => {
+                if let Some(client) = self.clients.get_mut(&centre.callsign)
+                    && let Some(slot) = client.secondary_centres.get_mut(centre.index)
+                {
+                    *slot = Some((centre.latitude, centre.longitude));
+                }
+            }
+            FsdMessageType::PilotPositionUpdateMessage(update) => self.move_pilot(
+                update.callsign,
+                (update.latitude, update.longitude),
+                update.true_altitude,
+            ),
+            FsdMessageType::VelocityPositionStoppedMessage(update) => self.move_pilot(
+                update.from,
+                (update.latitude, update.longitude),
+                update.true_altitude,
+            ),
+            FsdMessageType::VelocityPositionSlowMessage(update) => self.move_pilot(
+                update.from,
+                (update.latitude, update.longitude),
+                update.true_altitude,
+            ),
+            FsdMessageType::VelocityPositionFastMessage(update) => self.move_pilot(
+                update.from,
+                (update.latitude, update.longitude),
+                update.true_altitude,
+            ),
+            _ => {}
+        }
+    }
+
+    /// The clients that should receive `message`, in callsign order. The sender is never included.
+    ///
+    /// Call [`update`][Router::update] with the message first, so that a position update is routed from the
+    /// position it reports.
+    pub fn recipients(&self, message: &FsdMessageType) -> Vec<Callsign> {
+        let sender = message.sender();
+        let recipient = message.recipient();
+        let mut recipients: Vec<Callsign> = match recipient.as_deref() {
+            Some(Recipient::Callsign(callsign)) => self
+                .clients
+                .contains_key(callsign)
+                .then_some(*callsign)
+                .into_iter()
+                .collect(),
+            Some(Recipient::Supervisors) => self.select(sender, |_, client| is_supervisor(client)),
+            Some(Recipient::Server) | Some(Recipient::FlightPlanHandler) => Vec::new(),
+            Some(Recipient::AllAtc) | Some(Recipient::AircraftHandler) => self
+                .select(sender, |from, client| {
+                    is_atc(client) && in_range(from, client)
+                }),
+            Some(Recipient::AllPilots) => self.select(sender, |from, client| {
+                !is_atc(client) && in_range(from, client)
+            }),
+            Some(Recipient::Broadcast) | Some(Recipient::Frequencies(_)) => {
+                self.select(sender, in_range)
+            }
+            None if is_pilot_position(message) => self.select(sender, |from, client| {
+                from.position
+                    .is_some_and(|position| client.covers(position))
+            }),
+            None => self.select(sender, in_range),
+        };
+        recipients.sort_unstable();
+        recipients
+    }
+
+    /// Every client other than `sender` that `filter` accepts, given the sender's visibility
+    fn select(
+        &self,
+        sender: &str,
+        filter: impl Fn(&Visibility, &Visibility) -> bool,
+    ) -> Vec<Callsign> {
+        let Some(from) = self.clients.get(sender) else {
+            return Vec::new();
+        };
+        self.clients
+            .iter()
+            .filter(|(callsign, client)| callsign.as_str() != sender && filter(from, client))
+            .map(|(callsign, _)| *callsign)
+            .collect()
+    }
+
+    fn move_pilot(&mut self, callsign: Callsign, position: (f64, f64), altitude: f64) {
+        if let Some(client) = self.clients.get_mut(&callsign) {
+            client.position = Some(position);
+            client.range = pilot_range(altitude);
+        }
+    }
+}
+
+/// A controller's visibility range, capped at the maximum for its facility. A range of zero means the maximum.
+fn atc_range(update: &AtcPositionUpdateMessage) -> f64 {
+    let max_range = update.atc_type.max_range();
+    match u16::try_from(update.vis_range) {
+        Ok(0) => f64::from(max_range),
+        Ok(vis_range) => f64::from(vis_range.min(max_range)),
+        Err(_) => f64::from(max_range),
+    }
+}
+
+/// How far a pilot can see at `altitude` feet, from the original FSD server
+fn pilot_range(altitude: f64) -> f64 {
+    10.0 + 1.414 * altitude.max(0.0).sqrt()
+}
+
+/// Whether either client can see the other
+fn in_range(a: &Visibility, b: &Visibility) -> bool {
+    b.position.is_some_and(|position| a.covers(position))
+        || a.position.is_some_and(|position| b.covers(position))
+}
+
+fn is_atc(client: &Visibility) -> bool {
+    matches!(client.role, Role::Atc { .. })
+}
+
+fn is_supervisor(client: &Visibility) -> bool {
+    matches!(
+        client.role,
+        Role::Atc {
+            rating: AtcRating::Supervisor | AtcRating::Administrator
+        }
+    )
+}
+
+fn is_pilot_position(message: &FsdMessageType) -> bool {
+    matches!(
+        message,
+        FsdMessageType::PilotPositionUpdateMessage(_)
+            | FsdMessageType::VelocityPositionStoppedMessage(_)
+            | FsdMessageType::VelocityPositionSlowMessage(_)
+            | FsdMessageType::VelocityPositionFastMessage(_)
+    )
+}
+
+/// The great-circle distance between two points, in nautical miles
+fn distance_nm(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (a.0.to_radians(), b.0.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.1 - a.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * h.sqrt().min(1.0).asin()
+}
@@ -0,0 +1,613 @@
+//! An FSD server for private networks
+//!
+//! [`Server`] holds the state of the network without doing any I/O. Like
+//! [`ClientSession`][crate::session::ClientSession], it is fed the messages received from each connection and
+//! drained of what it wants to send with [`Server::poll_action`]. With the `tokio` feature, [`serve`] runs it on
+//! a TCP listener, and the `fsd-server` binary runs it from the command line.
+//!
+//! The server:
+//! - greets every connection with a `$DI` handshake, and expects a `$ID` handshake and a `#AP` or `#AA`
+//!   registration in reply, in that order
+//! - rejects a callsign that is already connected with [`FsdError::CallsignInUse`]
+//! - relays messages from registered clients to a callsign, or with a [`Router`] to every client, pilot (`*P`) or
+//!   controller (`*A`) in range. Position updates only reach the clients that can see the sender.
+//! - answers pings addressed to `SERVER`
+//! - tells the other clients with `#DP` or `#DA` when a client disconnects
+//!
+//! It doesn't check passwords, so it should only be run for a trusted group.
+//!
+//! # Example
+//! ```
+//! use std::time::Instant;
+//! use fsd_interface::server::{Server, ServerAction, ServerConfig};
+//! use fsd_interface::parse_message;
+//!
+//! let mut server = Server::new(ServerConfig::default());
+//! let now = Instant::now();
+//! let mut sent = |server: &mut Server| {
+//!     let mut lines = Vec::new();
+//!     while let Some(action) = server.poll_action() {
+//!         match action {
+//!             ServerAction::Send { to, message } => lines.push((to, message.to_string())),
+//!             ServerAction::Close(client) => lines.push((client, "closed".to_string())),
+//!         }
+//!     }
+//!     lines
+//! };
+//!
+//! let pilot = server.accept(now);
+//! assert!(sent(&mut server)[0].1.starts_with("$DISERVER:CLIENT:"));
+//! server.handle_message(pilot, parse_message("$IDBAW123:SERVER:69d7:EuroScope 3.2:3:2:1234567:").unwrap());
+//! server.handle_message(pilot, parse_message("#APBAW123:SERVER:1234567:password:1:9:1:Jane Doe").unwrap());
+//! assert!(sent(&mut server).is_empty());
+//!
+//! // A second client can't use the same callsign
+//! let imposter = server.accept(now);
+//! server.handle_message(imposter, parse_message("$IDBAW123:SERVER:69d7:EuroScope 3.2:3:2:7654321:").unwrap());
+//! let rejected = sent(&mut server);
+//! assert_eq!((imposter, "$ERSERVER:BAW123:001::".to_string()), rejected[1]);
+//! assert_eq!((imposter, "closed".to_string()), rejected[2]);
+//!
+//! let tower = server.accept(now);
+//! server.handle_message(tower, parse_message("$IDEGLL_TWR:SERVER:69d7:EuroScope 3.2:3:2:7654321:").unwrap());
+//! server.handle_message(tower, parse_message("#AAEGLL_TWR:SERVER:John Smith:7654321:password:5:9").unwrap());
+//! assert_eq!((pilot, "#AAEGLL_TWR:SERVER:John Smith:7654321::5:9".to_string()), sent(&mut server)[1]);
+//!
+//! server.handle_message(tower, parse_message("#TMEGLL_TWR:BAW123:Hello").unwrap());
+//! assert_eq!(vec![(pilot, "#TMEGLL_TWR:BAW123:Hello".to_string())], sent(&mut server));
+//!
+//! server.handle_disconnect(pilot);
+//! assert_eq!(vec![(tower, "#DPBAW123:1234567".to_string())], sent(&mut server));
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{BuildHasher, RandomState},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Callsign, Cid, FsdMessage, FsdMessageType, Recipient,
+    errors::FsdError,
+    messages::{
+        AtcDeregisterMessage, FsdErrorMessage, InitialServerHandshakeMessage,
+        PilotDeregisterMessage, PongMessage,
+    },
+    router::Router,
+    session::Role,
+};
+
+/// Identifies a connection to a [`Server`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClientId(u64);
+
+/// How a [`Server`] behaves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// The callsign the server sends its own messages from
+    pub callsign: Callsign,
+    /// Sent to clients in the `$DI` handshake
+    pub version: String,
+    /// How long a client has to register after connecting
+    pub handshake_timeout: Duration,
+    /// The most clients that can be registered at once. Unlimited if `None`.
+    pub max_clients: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            callsign: Callsign::from_static("SERVER"),
+            version: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")).to_string(),
+            handshake_timeout: Duration::from_secs(30),
+            max_clients: None,
+        }
+    }
+}
+
+/// Something the [`Server`] needs done by whatever is running its connections
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)] // Almost every action is a send, so boxing would only add an allocation
+pub enum ServerAction {
+    /// Send a message to a client
+    Send {
+        to: ClientId,
+        message: FsdMessageType,
+    },
+    /// Close a client's connection, once the messages already sent to it have been written
+    Close(ClientId),
+}
+
+/// A client that has registered with the [`Server`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredClient {
+    pub callsign: Callsign,
+    pub cid: Cid,
+    pub real_name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone)]
+enum ClientState {
+    AwaitingHandshake {
+        deadline: Instant,
+    },
+    AwaitingRegistration {
+        callsign: Callsign,
+        deadline: Instant,
+    },
+    Registered(RegisteredClient),
+}
+
+/// The state of an FSD network, independent of how its connections are run
+#[derive(Debug, Clone)]
+pub struct Server {
+    config: ServerConfig,
+    clients: HashMap<ClientId, ClientState>,
+    callsigns: HashMap<Callsign, ClientId>,
+    next_id: u64,
+    actions: VecDeque<ServerAction>,
+    keys: RandomState,
+    router: Router,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig) -> Self {
+        Server {
+            config,
+            clients: HashMap::new(),
+            callsigns: HashMap::new(),
+            next_id: 0,
+            actions: VecDeque::new(),
+            keys: RandomState::new(),
+            router: Router::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    /// The client registered with `callsign`, if there is one
+    pub fn client_by_callsign(&self, callsign: Callsign) -> Option<ClientId> {
+        self.callsigns.get(&callsign).copied()
+    }
+
+    /// The registration of `client`, if it has registered
+    pub fn registration(&self, client: ClientId) -> Option<&RegisteredClient> {
+        match self.clients.get(&client) {
+            Some(ClientState::Registered(registration)) => Some(registration),
+            _ => None,
+        }
+    }
+
+    /// Every registered client
+    pub fn registered_clients(&self) -> impl Iterator<Item = (ClientId, &RegisteredClient)> {
+        self.clients.iter().filter_map(|(id, state)| match state {
+            ClientState::Registered(registration) => Some((*id, registration)),
+            _ => None,
+        })
+    }
+
+    /// Starts tracking a connection opened at `now`, and sends it the server's handshake
+    pub fn accept(&mut self, now: Instant) -> ClientId {
+        let client = ClientId(self.next_id);
+        self.next_id += 1;
+        let deadline = now + self.config.handshake_timeout;
+        self.clients
+            .insert(client, ClientState::AwaitingHandshake { deadline });
+        let key = format!("{:012x}", self.keys.hash_one(client) & 0xffff_ffff_ffff);
+        let handshake = InitialServerHandshakeMessage::new(
+            self.config.callsign,
+            Callsign::from_static("CLIENT"),
+            self.config.version.as_str(),
+            key,
+        );
+        self.send(
+            client,
+            FsdMessageType::InitialServerHandshakeMessage(handshake),
+        );
+        client
+    }
+
+    /// Processes a message received from `client`
+    ///
+    /// A client that breaks the login flow is sent a fatal error and closed. If it had registered, the other
+    /// clients are told it has left, and its callsign is free to use again.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Instant;
+    /// use fsd_interface::server::{Server, ServerAction, ServerConfig};
+    /// use fsd_interface::{Callsign, parse_message};
+    ///
+    /// let mut server = Server::new(ServerConfig::default());
+    /// let mut sent = |server: &mut Server| {
+    ///     let mut lines = Vec::new();
+    ///     while let Some(action) = server.poll_action() {
+    ///         match action {
+    ///             ServerAction::Send { to, message } => lines.push((to, message.to_string())),
+    ///             ServerAction::Close(client) => lines.push((client, "closed".to_string())),
+    ///         }
+    ///     }
+    ///     lines
+    /// };
+    /// let login = |server: &mut Server, callsign: &str, cid: &str| {
+    ///     let client = server.accept(Instant::now());
+    ///     let handshake = format!("$ID{callsign}:SERVER:69d7:EuroScope 3.2:3:2:{cid}:");
+    ///     let registration = format!("#AP{callsign}:SERVER:{cid}:password:1:9:1:Jane Doe");
+    ///     server.handle_message(client, parse_message(handshake).unwrap());
+    ///     server.handle_message(client, parse_message(registration).unwrap());
+    ///     client
+    /// };
+    ///
+    /// let tower = login(&mut server, "EGLL_TWR", "7654321");
+    /// let pilot = login(&mut server, "BAW123", "1234567");
+    /// sent(&mut server);
+    ///
+    /// // Registering twice is an error, which also deregisters the client
+    /// let again = parse_message("#APBAW123:SERVER:1234567:password:1:9:1:Jane Doe").unwrap();
+    /// server.handle_message(pilot, again);
+    /// assert_eq!(
+    ///     vec![
+    ///         (pilot, "$ERSERVER:BAW123:003::".to_string()),
+    ///         (tower, "#DPBAW123:1234567".to_string()),
+    ///         (pilot, "closed".to_string()),
+    ///     ],
+    ///     sent(&mut server)
+    /// );
+    /// assert!(server.client_by_callsign(Callsign::from_static("BAW123")).is_none());
+    ///
+    /// // So the pilot can reconnect with the same callsign
+    /// let pilot = login(&mut server, "BAW123", "1234567");
+    /// assert_eq!(Some(pilot), server.client_by_callsign(Callsign::from_static("BAW123")));
+    /// ```
+    pub fn handle_message(&mut self, client: ClientId, message: FsdMessageType) {
+        let Some(state) = self.clients.get(&client) else {
+            return;
+        };
+        match (state.clone(), message) {
+            (
+                ClientState::AwaitingHandshake { deadline },
+                FsdMessageType::InitialClientHandshakeMessage(handshake),
+            ) => {
+                if self.check_callsign_free(client, handshake.from) {
+                    self.clients.insert(
+                        client,
+                        ClientState::AwaitingRegistration {
+                            callsign: handshake.from,
+                            deadline,
+                        },
+                    );
+                }
+            }
+            (
+                ClientState::AwaitingRegistration { callsign, .. },
+                FsdMessageType::PilotRegisterMessage(mut registration),
+            ) if registration.from == callsign => {
+                let role = Role::Pilot {
+                    rating: registration.rating,
+                    simulator_type: registration.simulator_type,
+                };
+                let (cid, real_name) = (registration.cid, registration.real_name.clone());
+                registration.password.clear();
+                let announcement = FsdMessageType::PilotRegisterMessage(registration);
+                self.register(client, callsign, cid, real_name, role, announcement);
+            }
+            (
+                ClientState::AwaitingRegistration { callsign, .. },
+                FsdMessageType::AtcRegisterMessage(mut registration),
+            ) if registration.from == callsign => {
+                let role = Role::Atc {
+                    rating: registration.rating,
+                };
+                let (cid, real_name) = (registration.cid, registration.real_name.clone());
+                registration.password.clear();
+                let announcement = FsdMessageType::AtcRegisterMessage(registration);
+                self.register(client, callsign, cid, real_name, role, announcement);
+            }
+            (ClientState::AwaitingHandshake { .. }, _)
+            | (ClientState::AwaitingRegistration { .. }, _) => {
+                self.reject(client, Recipient::Server, FsdError::SyntaxError);
+            }
+            (ClientState::Registered(registration), message) => {
+                self.handle_registered(client, registration, message)
+            }
+        }
+    }
+
+    /// Stops tracking a connection that has closed, telling the other clients if it had registered
+    pub fn handle_disconnect(&mut self, client: ClientId) {
+        if let Some(ClientState::Registered(registration)) = self.clients.remove(&client) {
+            self.callsigns.remove(&registration.callsign);
+            self.router.remove_client(registration.callsign);
+            let deregistration = match registration.role {
+                Role::Pilot { .. } => FsdMessageType::PilotDeregisterMessage(
+                    PilotDeregisterMessage::new(registration.callsign, registration.cid),
+                ),
+                Role::Atc { .. } => FsdMessageType::AtcDeregisterMessage(
+                    AtcDeregisterMessage::new(registration.callsign, registration.cid),
+                ),
+            };
+            self.relay_to_all(client, deregistration);
+        }
+    }
+
+    /// Disconnects every client that hasn't registered in time by `now`
+    pub fn handle_timeout(&mut self, now: Instant) {
+        let expired: Vec<ClientId> = self
+            .clients
+            .iter()
+            .filter(|(_, state)| handshake_deadline(state).is_some_and(|deadline| now >= deadline))
+            .map(|(client, _)| *client)
+            .collect();
+        for client in expired {
+            self.reject(client, Recipient::Server, FsdError::AuthTimeOut);
+        }
+    }
+
+    /// When [`handle_timeout`][Server::handle_timeout] next needs to be called, if at all
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        self.clients.values().filter_map(handshake_deadline).min()
+    }
+
+    /// The next thing for the server's runner to do
+    pub fn poll_action(&mut self) -> Option<ServerAction> {
+        self.actions.pop_front()
+    }
+
+    fn handle_registered(
+        &mut self,
+        client: ClientId,
+        registration: RegisteredClient,
+        message: FsdMessageType,
+    ) {
+        let callsign = registration.callsign;
+        if message == FsdMessageType::ServerHeartbeat {
+            return;
+        }
+        if !message.sender().eq_ignore_ascii_case(callsign.as_str()) {
+            self.send_error(client, callsign, FsdError::InvalidSourceCallsign);
+            return;
+        }
+        match message {
+            FsdMessageType::InitialClientHandshakeMessage(_)
+            | FsdMessageType::PilotRegisterMessage(_)
+            | FsdMessageType::AtcRegisterMessage(_) => {
+                self.reject(client, callsign.into(), FsdError::AlreadyRegistered);
+            }
+            FsdMessageType::PilotDeregisterMessage(_) | FsdMessageType::AtcDeregisterMessage(_) => {
+                self.handle_disconnect(client);
+                self.actions.push_back(ServerAction::Close(client));
+            }
+            FsdMessageType::PingMessage(ping) if ping.to == Recipient::Server => {
+                let pong = PongMessage::new(self.config.callsign, callsign, &ping.data);
+                self.send(client, FsdMessageType::PongMessage(pong));
+            }
+            message => self.route(client, callsign, message),
+        }
+    }
+
+    /// Relays a message from a registered client to its recipients, limiting broadcasts and position updates to
+    /// the clients in range
+    fn route(&mut self, client: ClientId, callsign: Callsign, message: FsdMessageType) {
+        self.router.update(&message);
+        if let Some(Recipient::Callsign(to)) = message.recipient().as_deref() {
+            match self.callsigns.get(to) {
+                Some(&target) => self.send(target, message),
+                None => self.send_error(client, callsign, FsdError::NoSuchCallsign(to.to_string())),
+            }
+            return;
+        }
+        for recipient in self.router.recipients(&message) {
+            if let Some(&target) = self.callsigns.get(&recipient) {
+                self.send(target, message.clone());
+            }
+        }
+    }
+
+    fn register(
+        &mut self,
+        client: ClientId,
+        callsign: Callsign,
+        cid: Cid,
+        real_name: String,
+        role: Role,
+        announcement: FsdMessageType,
+    ) {
+        if !self.check_callsign_free(client, callsign) {
+            return;
+        }
+        if self
+            .config
+            .max_clients
+            .is_some_and(|max_clients| self.callsigns.len() >= max_clients)
+        {
+            self.reject(client, callsign.into(), FsdError::ServerFull);
+            return;
+        }
+        self.callsigns.insert(callsign, client);
+        self.router.add_client(callsign, role);
+        self.clients.insert(
+            client,
+            ClientState::Registered(RegisteredClient {
+                callsign,
+                cid,
+                real_name,
+                role,
+            }),
+        );
+        self.relay_to_all(client, announcement);
+    }
+
+    /// Rejects `client` with [`FsdError::CallsignInUse`] if another client has registered `callsign`
+    fn check_callsign_free(&mut self, client: ClientId, callsign: Callsign) -> bool {
+        if self.callsigns.contains_key(&callsign) {
+            self.reject(client, callsign.into(), FsdError::CallsignInUse);
+            return false;
+        }
+        true
+    }
+
+    /// Sends `message` to every registered client other than `from`
+    fn relay_to_all(&mut self, from: ClientId, message: FsdMessageType) {
+        let mut recipients: Vec<ClientId> = self
+            .registered_clients()
+            .map(|(client, _)| client)
+            .filter(|client| *client != from)
+            .collect();
+        recipients.sort_unstable();
+        for to in recipients {
+            self.send(to, message.clone());
+        }
+    }
+
+    /// Sends a fatal error and closes the connection, deregistering the client if it had registered
+    fn reject(&mut self, client: ClientId, to: Recipient, error: FsdError) {
+        self.send_error(client, to, error);
+        self.handle_disconnect(client);
+        self.clients.remove(&client);
+        self.actions.push_back(ServerAction::Close(client));
+    }
+
+    fn send_error(&mut self, client: ClientId, to: impl Into<Recipient>, error: FsdError) {
+        let error = FsdErrorMessage::new(self.config.callsign, to, error);
+        self.send(client, FsdMessageType::FsdErrorMessage(error));
+    }
+
+    fn send(&mut self, to: ClientId, message: FsdMessageType) {
+        self.actions.push_back(ServerAction::Send { to, message });
+    }
+}
+
+fn handshake_deadline(state: &ClientState) -> Option<Instant> {
+    match state {
+        ClientState::AwaitingHandshake { deadline }
+        | ClientState::AwaitingRegistration { deadline, .. } => Some(*deadline),
+        ClientState::Registered(_) => None,
+    }
+}
+
+/// How many messages [`serve`] queues for a connection before closing it as too slow to keep up
+#[cfg(feature = "tokio")]
+pub const MAX_QUEUED_MESSAGES: usize = 1024;
+
+/// How long [`serve`] waits before accepting again after an error, such as running out of file descriptors
+#[cfg(feature = "tokio")]
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Runs a [`Server`] on `listener` until the returned future is dropped. Enabled with the `tokio` feature.
+///
+/// Each connection is read and written in its own task, while the server's state is kept in this one. A
+/// connection that falls [`MAX_QUEUED_MESSAGES`] behind is closed, and the other clients are told it has left.
+/// Errors accepting a connection are retried after a short pause.
+#[cfg(feature = "tokio")]
+pub async fn serve(listener: tokio::net::TcpListener, config: ServerConfig) {
+    use tokio::sync::mpsc::{self, error::TrySendError};
+
+    struct Connection {
+        outgoing: mpsc::Sender<FsdMessageType>,
+        task: tokio::task::AbortHandle,
+    }
+
+    let mut server = Server::new(config);
+    let (received, mut receiver) = mpsc::channel(MAX_QUEUED_MESSAGES);
+    let mut connections: HashMap<ClientId, Connection> = HashMap::new();
+    loop {
+        while let Some(action) = server.poll_action() {
+            match action {
+                ServerAction::Send { to, message } => {
+                    let Some(connection) = connections.get(&to) else {
+                        continue;
+                    };
+                    if let Err(TrySendError::Full(_)) = connection.outgoing.try_send(message) {
+                        connection.task.abort();
+                        connections.remove(&to);
+                        server.handle_disconnect(to);
+                    }
+                }
+                // Dropping the sender ends the connection's task once it has written what's queued
+                ServerAction::Close(client) => {
+                    connections.remove(&client);
+                }
+            }
+        }
+
+        let deadline = server.poll_timeout().map(tokio::time::Instant::from_std);
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let client = server.accept(Instant::now());
+                    let (sender, outgoing) = mpsc::channel(MAX_QUEUED_MESSAGES);
+                    let task = tokio::spawn(run_connection(client, stream, outgoing, received.clone()));
+                    connections.insert(
+                        client,
+                        Connection {
+                            outgoing: sender,
+                            task: task.abort_handle(),
+                        },
+                    );
+                }
+                Err(_) => tokio::time::sleep(ACCEPT_RETRY_DELAY).await,
+            },
+            Some((client, message)) = receiver.recv() => match message {
+                Some(message) => server.handle_message(client, message),
+                None => {
+                    connections.remove(&client);
+                    server.handle_disconnect(client);
+                }
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() => {
+                server.handle_timeout(Instant::now());
+            }
+        }
+    }
+}
+
+/// Passes messages between one connection and the server, sending `None` once the connection has closed
+#[cfg(feature = "tokio")]
+async fn run_connection(
+    client: ClientId,
+    stream: tokio::net::TcpStream,
+    mut outgoing: tokio::sync::mpsc::Receiver<FsdMessageType>,
+    received: tokio::sync::mpsc::Sender<(ClientId, Option<FsdMessageType>)>,
+) {
+    use bytes::BytesMut;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    let (mut reader, mut writer) = stream.into_split();
+    let mut codec = crate::FsdCodec::new();
+    let mut incoming = BytesMut::with_capacity(4096);
+    let mut buffer = BytesMut::new();
+    loop {
+        tokio::select! {
+            read = reader.read_buf(&mut incoming) => {
+                if !matches!(read, Ok(read) if read > 0) {
+                    break;
+                }
+                // Lines that can't be decoded are skipped. Reading stops while the server is behind.
+                while let Ok(Some(decoded)) = codec.decode(&mut incoming) {
+                    if let Ok(message) = decoded
+                        && received.send((client, Some(message))).await.is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+            message = outgoing.recv() => {
+                let Some(message) = message else {
+                    // The server closed the connection
+                    return;
+                };
+                buffer.clear();
+                if codec.encode(message, &mut buffer).is_ok()
+                    && writer.write_all(&buffer).await.is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = received.send((client, None)).await;
+}
@@ -0,0 +1,511 @@
+//! A transport-agnostic FSD client session
+//!
+//! [`ClientSession`] drives the login flow and the protocol housekeeping of a client connection without
+//! touching a socket. Parsed messages are fed in with [`ClientSession::handle_message`], and the messages it
+//! wants to send are drained with [`ClientSession::poll_transmit`]. Time is passed in explicitly, so timeouts
+//! can be tested without waiting for them.
+//!
+//! The session:
+//! - answers the server's `$DI` handshake with a `$ID` handshake, followed by a `#AP` or `#AA` registration
+//! - answers pings addressed to it with pongs
+//! - closes on a fatal `$ER` error, or when it is killed with `$!!`
+//! - reports a `$XX` request to move to another server, for the caller to reconnect
+//! - closes if the server doesn't send its handshake in time, or, optionally, goes quiet for too long
+//!
+//! Every message is still returned to the caller to handle as it likes. The session only looks at them.
+//!
+//! # Example
+//! ```
+//! use std::time::{Duration, Instant};
+//! use fsd_interface::{Callsign, Cid, PilotRating, SimulatorType, parse_message};
+//! use fsd_interface::session::{ClientSession, CloseReason, SessionConfig, SessionEvent};
+//!
+//! let config = SessionConfig::pilot(
+//!     Callsign::new("BAW123").unwrap(),
+//!     Cid::new("1234567").unwrap(),
+//!     "password",
+//!     "Jane Doe",
+//!     PilotRating::Student,
+//!     SimulatorType::Unknown,
+//! );
+//! let start = Instant::now();
+//! let mut session = ClientSession::new(config, start);
+//!
+//! let handshake = parse_message("$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6").unwrap();
+//! session.handle_message(&handshake, start);
+//! assert!(session.poll_transmit().unwrap().to_string().starts_with("$IDBAW123:SERVER:"));
+//! assert_eq!(
+//!     "#APBAW123:SERVER:1234567:password:1:9:0:Jane Doe",
+//!     session.poll_transmit().unwrap().to_string()
+//! );
+//! assert!(matches!(session.poll_event(), Some(SessionEvent::Registered { .. })));
+//!
+//! let ping = parse_message("$PISERVER:BAW123:8415").unwrap();
+//! session.handle_message(&ping, start + Duration::from_secs(1));
+//! assert_eq!("$POBAW123:SERVER:8415", session.poll_transmit().unwrap().to_string());
+//!
+//! let error = parse_message("$ERserver:BAW123:6:BAW123:Invalid CID/password").unwrap();
+//! session.handle_message(&error, start + Duration::from_secs(2));
+//! assert!(session.is_closed());
+//! assert!(matches!(session.poll_event(), Some(SessionEvent::Closed(CloseReason::Error(_)))));
+//! ```
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    AtcRating, Callsign, Cid, FsdMessageType, PilotRating, ProtocolRevision, Recipient,
+    SimulatorType,
+    errors::FsdError,
+    messages::{
+        AtcDeregisterMessage, AtcRegisterMessage, InitialClientHandshakeMessage,
+        PilotDeregisterMessage, PilotRegisterMessage, PongMessage,
+    },
+};
+
+/// The TCP port FSD servers listen on
+pub const DEFAULT_PORT: u16 = 6809;
+
+/// How long [`SessionConfig::handshake_timeout`] waits for the server's handshake by default
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether the client connects as a pilot or a controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    Pilot {
+        rating: PilotRating,
+        simulator_type: SimulatorType,
+    },
+    Atc {
+        rating: AtcRating,
+    },
+}
+
+/// Everything a [`ClientSession`] needs to log in
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionConfig {
+    pub callsign: Callsign,
+    pub cid: Cid,
+    pub password: String,
+    pub real_name: String,
+    pub role: Role,
+    /// Sent in the registration. Defaults to [`ProtocolRevision::Classic`], for private servers running classic
+    /// FSD. VATSIM servers reject it, and need [`ProtocolRevision::Vatsim2022`], which
+    /// [`Dialect::protocol_revision`][crate::Dialect::protocol_revision] gives for each dialect.
+    pub protocol: ProtocolRevision,
+    pub client_id: u16,
+    pub client_name: String,
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub guid: String,
+    /// Appended to the client handshake. VATSIM servers expect the client's initial authentication challenge here.
+    pub initial_key: Option<String>,
+    /// How long to wait for the server's handshake before giving up
+    pub handshake_timeout: Duration,
+    /// How long the server can stay silent once registered before the session is closed. Never, by default.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl SessionConfig {
+    /// Creates a configuration for a pilot client, identifying itself as this crate
+    pub fn pilot(
+        callsign: Callsign,
+        cid: Cid,
+        password: impl Into<String>,
+        real_name: impl Into<String>,
+        rating: PilotRating,
+        simulator_type: SimulatorType,
+    ) -> Self {
+        SessionConfig::new(
+            callsign,
+            cid,
+            password.into(),
+            real_name.into(),
+            Role::Pilot {
+                rating,
+                simulator_type,
+            },
+        )
+    }
+
+    /// Creates a configuration for a controller client, identifying itself as this crate
+    pub fn atc(
+        callsign: Callsign,
+        cid: Cid,
+        password: impl Into<String>,
+        real_name: impl Into<String>,
+        rating: AtcRating,
+    ) -> Self {
+        SessionConfig::new(
+            callsign,
+            cid,
+            password.into(),
+            real_name.into(),
+            Role::Atc { rating },
+        )
+    }
+
+    fn new(callsign: Callsign, cid: Cid, password: String, real_name: String, role: Role) -> Self {
+        SessionConfig {
+            callsign,
+            cid,
+            password,
+            real_name,
+            role,
+            protocol: ProtocolRevision::Classic,
+            client_id: 0,
+            client_name: env!("CARGO_PKG_NAME").to_string(),
+            major_version: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or_default(),
+            minor_version: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or_default(),
+            guid: String::new(),
+            initial_key: None,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            idle_timeout: None,
+        }
+    }
+}
+
+/// Where a [`ClientSession`] is in its lifecycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionState {
+    /// Waiting for the server's `$DI` handshake
+    AwaitingHandshake,
+    /// The client handshake and registration have been sent
+    Registered,
+    /// The session has ended, and nothing more will be sent
+    Closed(CloseReason),
+}
+
+/// Why a [`ClientSession`] closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The server sent a fatal error
+    Error(FsdError),
+    /// The client was kicked off the network
+    Killed {
+        from: Callsign,
+        reason: Option<String>,
+    },
+    /// The server didn't send its handshake within [`SessionConfig::handshake_timeout`]
+    HandshakeTimedOut,
+    /// Nothing was received within [`SessionConfig::idle_timeout`]
+    IdleTimedOut,
+    /// [`ClientSession::disconnect`] was called
+    Disconnected,
+}
+
+/// Something that happened to a [`ClientSession`], for the application to act on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// The server's handshake was answered and the client registered
+    Registered { server_version: String },
+    /// The server reported an error that doesn't end the session
+    Error(FsdError),
+    /// The server asked the client to reconnect to another server. The caller should open a new connection to
+    /// `hostname`, on the same port, and start a new session on it.
+    ChangeServer { hostname: String },
+    /// The session has ended. Any messages still waiting in [`ClientSession::poll_transmit`] should be sent
+    /// before the connection is closed.
+    Closed(CloseReason),
+}
+
+/// The protocol state of one client connection, independent of how messages are sent and received
+#[derive(Debug, Clone)]
+pub struct ClientSession {
+    config: SessionConfig,
+    state: SessionState,
+    outgoing: VecDeque<FsdMessageType>,
+    events: VecDeque<SessionEvent>,
+    handshake_deadline: Instant,
+    last_received: Instant,
+}
+
+impl ClientSession {
+    /// Creates a session for a connection opened at `now`
+    pub fn new(config: SessionConfig, now: Instant) -> Self {
+        ClientSession {
+            handshake_deadline: now + config.handshake_timeout,
+            config,
+            state: SessionState::AwaitingHandshake,
+            outgoing: VecDeque::new(),
+            events: VecDeque::new(),
+            last_received: now,
+        }
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self.state, SessionState::Closed(_))
+    }
+
+    /// Processes a message received from the server at `now`
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use fsd_interface::{Callsign, Cid, PilotRating, SimulatorType, parse_message};
+    /// # use fsd_interface::session::*;
+    /// # let config = SessionConfig::pilot(
+    /// #     Callsign::new("BAW123").unwrap(),
+    /// #     Cid::new("1234567").unwrap(),
+    /// #     "password",
+    /// #     "Jane Doe",
+    /// #     PilotRating::Student,
+    /// #     SimulatorType::Unknown,
+    /// # );
+    /// let start = Instant::now();
+    /// let mut session = ClientSession::new(config, start);
+    /// let handshake = parse_message("$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6").unwrap();
+    /// session.handle_message(&handshake, start);
+    /// while session.poll_transmit().is_some() {}
+    /// assert!(matches!(session.poll_event(), Some(SessionEvent::Registered { .. })));
+    ///
+    /// // A second handshake is ignored rather than registering again
+    /// session.handle_message(&handshake, start);
+    /// assert!(session.poll_transmit().is_none());
+    /// assert!(session.poll_event().is_none());
+    ///
+    /// // Errors that aren't fatal are reported, and the session carries on
+    /// let error = parse_message("$ERserver:BAW123:9:EGLL_TWR:No such callsign").unwrap();
+    /// session.handle_message(&error, start);
+    /// assert!(matches!(session.poll_event(), Some(SessionEvent::Error(_))));
+    /// assert_eq!(&SessionState::Registered, session.state());
+    ///
+    /// // Being killed closes it
+    /// let kill = parse_message("$!!SERVER:BAW123:Flying on the runway").unwrap();
+    /// session.handle_message(&kill, start);
+    /// let Some(SessionEvent::Closed(CloseReason::Killed { reason, .. })) = session.poll_event() else {
+    ///     panic!("expected the session to close");
+    /// };
+    /// assert_eq!(Some("Flying on the runway".to_string()), reason);
+    /// ```
+    pub fn handle_message(&mut self, message: &FsdMessageType, now: Instant) {
+        if self.is_closed() {
+            return;
+        }
+        self.last_received = now;
+        match message {
+            FsdMessageType::InitialServerHandshakeMessage(handshake)
+                if self.state == SessionState::AwaitingHandshake =>
+            {
+                self.register();
+                self.state = SessionState::Registered;
+                self.events.push_back(SessionEvent::Registered {
+                    server_version: handshake.version.clone(),
+                });
+            }
+            FsdMessageType::PingMessage(ping)
+                if self.state == SessionState::Registered && self.is_for_us(&ping.to) =>
+            {
+                let pong = PongMessage::new(self.config.callsign, ping.from, &ping.data);
+                self.outgoing.push_back(FsdMessageType::PongMessage(pong));
+            }
+            FsdMessageType::FsdErrorMessage(error) if error.error_type.is_fatal() => {
+                self.close(CloseReason::Error(error.error_type.clone()));
+            }
+            FsdMessageType::FsdErrorMessage(error) => {
+                self.events
+                    .push_back(SessionEvent::Error(error.error_type.clone()));
+            }
+            FsdMessageType::ChangeServerMessage(change)
+                if self.state == SessionState::Registered && self.is_for_us(&change.to) =>
+            {
+                self.events.push_back(SessionEvent::ChangeServer {
+                    hostname: change.hostname.clone(),
+                });
+            }
+            FsdMessageType::KillMessage(kill) if self.is_for_us(&kill.to) => {
+                self.close(CloseReason::Killed {
+                    from: kill.from,
+                    reason: kill.reason.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Closes the session if a deadline has passed by `now`. Call this when the time returned by
+    /// [`poll_timeout`][ClientSession::poll_timeout] is reached.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use fsd_interface::{Callsign, Cid, PilotRating, SimulatorType, parse_message};
+    /// # use fsd_interface::session::*;
+    /// # let config = SessionConfig::pilot(
+    /// #     Callsign::new("BAW123").unwrap(),
+    /// #     Cid::new("1234567").unwrap(),
+    /// #     "password",
+    /// #     "Jane Doe",
+    /// #     PilotRating::Student,
+    /// #     SimulatorType::Unknown,
+    /// # );
+    /// let start = Instant::now();
+    /// let mut session = ClientSession::new(config.clone(), start);
+    /// assert_eq!(Some(start + DEFAULT_HANDSHAKE_TIMEOUT), session.poll_timeout());
+    /// session.handle_timeout(start + DEFAULT_HANDSHAKE_TIMEOUT);
+    /// assert_eq!(&SessionState::Closed(CloseReason::HandshakeTimedOut), session.state());
+    ///
+    /// // Once registered, the session closes if the server stays quiet for longer than the idle timeout
+    /// let config = SessionConfig {
+    ///     idle_timeout: Some(Duration::from_secs(60)),
+    ///     ..config
+    /// };
+    /// let mut session = ClientSession::new(config, start);
+    /// let handshake = parse_message("$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6").unwrap();
+    /// session.handle_message(&handshake, start);
+    /// let ping = parse_message("$PISERVER:BAW123:8415").unwrap();
+    /// session.handle_message(&ping, start + Duration::from_secs(30));
+    /// session.handle_timeout(start + Duration::from_secs(60));
+    /// assert_eq!(&SessionState::Registered, session.state());
+    /// session.handle_timeout(start + Duration::from_secs(90));
+    /// assert_eq!(&SessionState::Closed(CloseReason::IdleTimedOut), session.state());
+    /// ```
+    pub fn handle_timeout(&mut self, now: Instant) {
+        match self.poll_timeout() {
+            Some(deadline) if now >= deadline => {
+                let reason = match self.state {
+                    SessionState::AwaitingHandshake => CloseReason::HandshakeTimedOut,
+                    _ => CloseReason::IdleTimedOut,
+                };
+                self.close(reason);
+            }
+            _ => {}
+        }
+    }
+
+    /// When [`handle_timeout`][ClientSession::handle_timeout] next needs to be called, if at all
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        match self.state {
+            SessionState::AwaitingHandshake => Some(self.handshake_deadline),
+            SessionState::Registered => self
+                .config
+                .idle_timeout
+                .map(|timeout| self.last_received + timeout),
+            SessionState::Closed(_) => None,
+        }
+    }
+
+    /// The next message to send to the server
+    pub fn poll_transmit(&mut self) -> Option<FsdMessageType> {
+        self.outgoing.pop_front()
+    }
+
+    /// The next event for the application
+    pub fn poll_event(&mut self) -> Option<SessionEvent> {
+        self.events.pop_front()
+    }
+
+    /// Ends the session, deregistering from the server if the client had registered
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use fsd_interface::{Callsign, Cid, PilotRating, SimulatorType, parse_message};
+    /// # use fsd_interface::session::*;
+    /// # let config = SessionConfig::pilot(
+    /// #     Callsign::new("BAW123").unwrap(),
+    /// #     Cid::new("1234567").unwrap(),
+    /// #     "password",
+    /// #     "Jane Doe",
+    /// #     PilotRating::Student,
+    /// #     SimulatorType::Unknown,
+    /// # );
+    /// // Nothing is sent before the client has registered
+    /// let mut session = ClientSession::new(config.clone(), Instant::now());
+    /// session.disconnect();
+    /// assert!(session.poll_transmit().is_none());
+    /// assert_eq!(&SessionState::Closed(CloseReason::Disconnected), session.state());
+    ///
+    /// let mut session = ClientSession::new(config, Instant::now());
+    /// let handshake = parse_message("$DISERVER:CLIENT:VATSIM FSD V3.43:a1b2c3d4e5f6").unwrap();
+    /// session.handle_message(&handshake, Instant::now());
+    /// while session.poll_transmit().is_some() {}
+    /// session.disconnect();
+    /// assert_eq!("#DPBAW123:1234567", session.poll_transmit().unwrap().to_string());
+    /// assert_eq!(&SessionState::Closed(CloseReason::Disconnected), session.state());
+    /// ```
+    pub fn disconnect(&mut self) {
+        if self.state == SessionState::Registered {
+            let (callsign, cid) = (self.config.callsign, self.config.cid);
+            let deregister = match self.config.role {
+                Role::Pilot { .. } => FsdMessageType::PilotDeregisterMessage(
+                    PilotDeregisterMessage::new(callsign, cid),
+                ),
+                Role::Atc { .. } => {
+                    FsdMessageType::AtcDeregisterMessage(AtcDeregisterMessage::new(callsign, cid))
+                }
+            };
+            self.outgoing.push_back(deregister);
+        }
+        if !self.is_closed() {
+            self.close(CloseReason::Disconnected);
+        }
+    }
+
+    fn is_for_us(&self, to: &Recipient) -> bool {
+        match to {
+            Recipient::Callsign(callsign) => *callsign == self.config.callsign,
+            Recipient::Broadcast => true,
+            _ => false,
+        }
+    }
+
+    fn register(&mut self) {
+        let config = &self.config;
+        let handshake = InitialClientHandshakeMessage::new(
+            config.callsign,
+            Recipient::Server,
+            config.client_id,
+            config.client_name.as_str(),
+            config.major_version,
+            config.minor_version,
+            config.cid,
+            config.guid.as_str(),
+            config.initial_key.as_deref(),
+        );
+        let registration = match config.role {
+            Role::Pilot {
+                rating,
+                simulator_type,
+            } => FsdMessageType::PilotRegisterMessage(PilotRegisterMessage::new(
+                config.callsign,
+                Recipient::Server,
+                config.real_name.as_str(),
+                config.cid,
+                config.password.as_str(),
+                rating,
+                config.protocol,
+                simulator_type,
+            )),
+            Role::Atc { rating } => FsdMessageType::AtcRegisterMessage(AtcRegisterMessage::new(
+                config.callsign,
+                Recipient::Server,
+                config.real_name.as_str(),
+                config.cid,
+                config.password.as_str(),
+                rating,
+                config.protocol,
+            )),
+        };
+        self.outgoing
+            .push_back(FsdMessageType::InitialClientHandshakeMessage(handshake));
+        self.outgoing.push_back(registration);
+    }
+
+    fn close(&mut self, reason: CloseReason) {
+        self.state = SessionState::Closed(reason.clone());
+        self.events.push_back(SessionEvent::Closed(reason));
+    }
+}
@@ -1,8 +1,14 @@
 use std::{fmt::Display, str::FromStr};
 
-use crate::{enums::FlightRules, errors::FsdMessageParseError, Level};
+use crate::{
+    enums::FlightRules,
+    errors::FsdMessageParseError,
+    options::{ParseContext, ParseFields},
+    util::{self, check_exact_num_fields, impl_try_from_fields, parse_field, parse_field_or},
+    Level,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TransponderCode(u16);
 impl TryFrom<u16> for TransponderCode {
     type Error = FsdMessageParseError;
@@ -42,8 +48,9 @@ impl TransponderCode {
         Self::try_from(result)
     }
     pub fn as_bcd_format(&self) -> u32 {
-        let as_dec_str = self.to_string();
-        u32::from_str_radix(&as_dec_str, 16).unwrap()
+        // Each decimal digit of the code becomes a hex digit
+        let code = self.0 as u32;
+        ((code / 1000) << 12) | ((code / 100 % 10) << 8) | ((code / 10 % 10) << 4) | (code % 10)
     }
 }
 
@@ -62,10 +69,25 @@ impl Display for TransponderCode {
     }
 }
 
+/// Serialised as the four digit code, for example `"7000"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for TransponderCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TransponderCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Represents a VHF, airband radio frequency from 118.000 MHz to 137.000 MHz.
 ///
 /// Stored internally as the left part and the right part. For example, 118.3MHz is `RadioFrequency(118, 300)`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RadioFrequency(pub(crate) u16, pub(crate) u16);
 impl RadioFrequency {
     /// Creates a new [`RadioFrequency`] from two parts
@@ -130,7 +152,8 @@ impl RadioFrequency {
 impl FromStr for RadioFrequency {
     type Err = FsdMessageParseError;
     fn from_str(short_form: &str) -> Result<Self, Self::Err> {
-        if short_form.len() != 5 {
+        // Checked before slicing, as a multi-byte character could straddle the split
+        if !short_form.is_ascii() || short_form.len() != 5 {
             return Err(FsdMessageParseError::InvalidFrequency(
                 short_form.to_string(),
             ));
@@ -151,7 +174,23 @@ impl Display for RadioFrequency {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+/// Serialised in human readable form, for example `"118.300"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for RadioFrequency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_human_readable_string())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RadioFrequency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let frequency = String::deserialize(deserializer)?;
+        RadioFrequency::try_from_human_readable_string(frequency).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaneInfo {
     pub equipment: Option<String>,
     pub airline: Option<String>,
@@ -228,7 +267,8 @@ impl PlaneInfo {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlightPlan {
     pub flight_rules: FlightRules,
     pub ac_type: String,
@@ -271,79 +311,57 @@ impl Display for FlightPlan {
     }
 }
 
-impl TryFrom<&[&str]> for FlightPlan {
-    type Error = FsdMessageParseError;
-    fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
-        if fields.len() != 15 {
-            return Err(FsdMessageParseError::InvalidFieldCount(15, fields.len()));
-        }
+impl_try_from_fields!(FlightPlan);
 
-        let filed_tas = if fields[2].is_empty() {
-            0
-        } else {
-            fields[2]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidSpeed(fields[2].to_string()))?
-        };
-        let etd = if fields[4].is_empty() {
-            0
-        } else {
-            fields[4]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[4].to_string()))?
-        };
-        let atd = if fields[5].is_empty() {
-            0
-        } else {
-            fields[5]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[5].to_string()))?
-        };
-        let hours_enroute = if fields[8].is_empty() {
-            0
-        } else {
-            fields[8]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[8].to_string()))?
-        };
-        let hours_fuel = if fields[10].is_empty() {
-            0
-        } else {
-            fields[10]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[10].to_string()))?
-        };
-        let mins_enroute = if fields[9].is_empty() {
-            0
-        } else {
-            let mins = fields[9]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[9].to_string()))?;
-            if mins > 59 {
-                return Err(FsdMessageParseError::InvalidMinute(fields[9].to_string()));
+impl ParseFields<'_> for FlightPlan {
+    fn parse_fields(fields: &[&str], cx: &mut ParseContext) -> Result<Self, FsdMessageParseError> {
+        check_exact_num_fields!(cx, fields, 15);
+
+        fn number_or_zero<T: FromStr + Default>(
+            fields: &[&str],
+            index: usize,
+            name: &'static str,
+            error: fn(String) -> FsdMessageParseError,
+        ) -> Result<T, FsdMessageParseError> {
+            if fields[index].is_empty() {
+                Ok(T::default())
+            } else {
+                parse_field_or(fields, index, name, error)
             }
-            mins
-        };
-        let mins_fuel = if fields[11].is_empty() {
-            0
-        } else {
-            let mins = fields[11]
-                .parse()
-                .map_err(|_| FsdMessageParseError::InvalidTime(fields[11].to_string()))?;
+        }
+        let minutes_or_zero = |index: usize, name: &'static str| {
+            let mins: u8 = number_or_zero(fields, index, name, FsdMessageParseError::InvalidTime)?;
             if mins > 59 {
-                return Err(FsdMessageParseError::InvalidMinute(fields[11].to_string()));
+                return Err(
+                    FsdMessageParseError::InvalidMinute(fields[index].to_string())
+                        .at_field(fields, index, name),
+                );
             }
-            mins
+            Ok(mins)
         };
 
+        let filed_tas = number_or_zero(fields, 2, "filed_tas", FsdMessageParseError::InvalidSpeed)?;
+        let etd = number_or_zero(fields, 4, "etd", FsdMessageParseError::InvalidTime)?;
+        let atd = number_or_zero(fields, 5, "atd", FsdMessageParseError::InvalidTime)?;
+        let hours_enroute = number_or_zero(
+            fields,
+            8,
+            "hours_enroute",
+            FsdMessageParseError::InvalidTime,
+        )?;
+        let hours_fuel =
+            number_or_zero(fields, 10, "hours_fuel", FsdMessageParseError::InvalidTime)?;
+        let mins_enroute = minutes_or_zero(9, "mins_enroute")?;
+        let mins_fuel = minutes_or_zero(11, "mins_fuel")?;
+
         Ok(FlightPlan::new(
-            fields[0].parse()?,
+            parse_field(fields, 0, "flight_rules")?,
             fields[1],
             filed_tas,
             fields[3],
             etd,
             atd,
-            fields[6].parse()?,
+            util::parse_level(cx, fields, 6, "cruise_level")?,
             fields[7],
             hours_enroute,
             mins_enroute,
@@ -393,3 +411,114 @@ impl FlightPlan {
         }
     }
 }
+
+/// A temperature layer in a legacy FSD weather profile
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TemperatureLayer {
+    /// Top of the layer in feet
+    pub ceiling: i32,
+    /// Temperature in degrees Celsius
+    pub temperature: i32,
+}
+
+impl TemperatureLayer {
+    /// Parses a layer from the two fields starting at `index`
+    pub(crate) fn from_fields(fields: &[&str], index: usize) -> Result<Self, FsdMessageParseError> {
+        let error = FsdMessageParseError::InvalidWeatherValue;
+        Ok(TemperatureLayer {
+            ceiling: parse_field_or(fields, index, "ceiling", error)?,
+            temperature: parse_field_or(fields, index + 1, "temperature", error)?,
+        })
+    }
+}
+
+impl Display for TemperatureLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.ceiling, self.temperature)
+    }
+}
+
+/// A wind layer in a legacy FSD weather profile
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindLayer {
+    /// Top of the layer in feet
+    pub ceiling: i32,
+    /// Bottom of the layer in feet
+    pub floor: i32,
+    /// Direction the wind is blowing from, in degrees
+    pub direction: u16,
+    /// Speed in knots
+    pub speed: u16,
+    pub gusting: bool,
+    pub turbulence: u16,
+}
+
+impl WindLayer {
+    /// Parses a layer from the six fields starting at `index`
+    pub(crate) fn from_fields(fields: &[&str], index: usize) -> Result<Self, FsdMessageParseError> {
+        let error = FsdMessageParseError::InvalidWeatherValue;
+        Ok(WindLayer {
+            ceiling: parse_field_or(fields, index, "ceiling", error)?,
+            floor: parse_field_or(fields, index + 1, "floor", error)?,
+            direction: parse_field_or(fields, index + 2, "direction", error)?,
+            speed: parse_field_or(fields, index + 3, "speed", error)?,
+            gusting: parse_field_or::<u8>(fields, index + 4, "gusting", error)? == 1,
+            turbulence: parse_field_or(fields, index + 5, "turbulence", error)?,
+        })
+    }
+}
+
+impl Display for WindLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}:{}",
+            self.ceiling,
+            self.floor,
+            self.direction,
+            self.speed,
+            self.gusting as u8,
+            self.turbulence
+        )
+    }
+}
+
+/// A cloud layer in a legacy FSD weather profile
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CloudLayer {
+    /// Top of the layer in feet
+    pub ceiling: i32,
+    /// Bottom of the layer in feet
+    pub floor: i32,
+    /// Coverage in octals, from 0 to 8
+    pub coverage: u8,
+    pub icing: bool,
+    pub turbulence: u16,
+}
+
+impl CloudLayer {
+    /// Parses a layer from the five fields starting at `index`
+    pub(crate) fn from_fields(fields: &[&str], index: usize) -> Result<Self, FsdMessageParseError> {
+        let error = FsdMessageParseError::InvalidWeatherValue;
+        Ok(CloudLayer {
+            ceiling: parse_field_or(fields, index, "ceiling", error)?,
+            floor: parse_field_or(fields, index + 1, "floor", error)?,
+            coverage: parse_field_or(fields, index + 2, "coverage", error)?,
+            icing: parse_field_or::<u8>(fields, index + 3, "icing", error)? == 1,
+            turbulence: parse_field_or(fields, index + 4, "turbulence", error)?,
+        })
+    }
+}
+
+impl Display for CloudLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}",
+            self.ceiling, self.floor, self.coverage, self.icing as u8, self.turbulence
+        )
+    }
+}
@@ -1,6 +1,217 @@
-use crate::{enums::ClientCapability, errors::FsdMessageParseError, structs::RadioFrequency};
+use crate::{
+    enums::{ClientCapability, Level, Recipient},
+    errors::FsdMessageParseError,
+    identifiers::{Callsign, Identifier},
+    options::{ParseContext, ParseWarning},
+    prefix::Prefix,
+    structs::RadioFrequency,
+};
 use std::str::FromStr;
 
+/// Returns the known message prefix at the start of `first_field`, if any
+pub(crate) fn message_prefix(first_field: &str) -> Option<&'static str> {
+    Prefix::of(first_field).map(Prefix::as_str)
+}
+
+macro_rules! check_min_num_fields {
+    ($fields: ident, $i: literal) => {
+        if $fields.len() < $i {
+            return Err(FsdMessageParseError::InvalidFieldCount($i, $fields.len()).in_line($fields));
+        }
+    };
+}
+pub(crate) use check_min_num_fields;
+
+/// Requires between `$min` and `$max` fields. Extra fields are a deviation, which is only accepted in lenient mode.
+macro_rules! check_num_fields {
+    ($cx: ident, $fields: ident, $min: literal ..= $max: literal) => {
+        if $fields.len() < $min {
+            return Err(
+                FsdMessageParseError::InvalidFieldCount($min, $fields.len()).in_line($fields)
+            );
+        }
+        $cx.check_max_fields($fields, $max)?;
+    };
+}
+pub(crate) use check_num_fields;
+
+/// Requires exactly `$i` fields. Extra fields are a deviation, which is only accepted with lenient options.
+macro_rules! check_exact_num_fields {
+    ($cx: ident, $fields: ident, $i: literal) => {
+        if $fields.len() < $i {
+            return Err(FsdMessageParseError::InvalidFieldCount($i, $fields.len()).in_line($fields));
+        }
+        $cx.check_fixed_fields($fields, $i)?;
+    };
+}
+pub(crate) use check_exact_num_fields;
+
+/// Implements `TryFrom<&[&str]>` for types that implement [`ParseFields`][crate::options::ParseFields], parsing
+/// with the same default options as [`parse_message`][crate::parse_message]
+macro_rules! impl_try_from_fields {
+    ($($t: ty),* $(,)?) => {
+        $(
+            impl TryFrom<&[&str]> for $t {
+                type Error = FsdMessageParseError;
+                fn try_from(fields: &[&str]) -> Result<Self, Self::Error> {
+                    crate::options::ParseContext::default().parse(fields)
+                }
+            }
+        )*
+    };
+}
+pub(crate) use impl_try_from_fields;
+
+/// Returns the first field with its `prefix_len`-byte message prefix removed
+#[inline]
+pub(crate) fn strip_prefix<'a>(
+    fields: &[&'a str],
+    prefix_len: usize,
+) -> Result<&'a str, FsdMessageParseError> {
+    let first = field(fields, 0, "prefix")?;
+    first.get(prefix_len..).ok_or_else(|| {
+        FsdMessageParseError::UnknownMessageType(first.to_string()).at_field(fields, 0, "prefix")
+    })
+}
+
+/// Returns field `index`, or an error with context if there are not enough fields
+#[inline]
+pub(crate) fn field<'a>(
+    fields: &[&'a str],
+    index: usize,
+    name: &'static str,
+) -> Result<&'a str, FsdMessageParseError> {
+    fields.get(index).copied().ok_or_else(|| {
+        FsdMessageParseError::InvalidFieldCount(index + 1, fields.len())
+            .at_field(fields, index, name)
+    })
+}
+
+/// Parses field `index` as a [`Recipient`].
+///
+/// An invalid recipient is a deviation. In lenient mode, it is kept as a [`Recipient::Callsign`] as long as it
+/// can still be sent back.
+pub(crate) fn parse_recipient(
+    cx: &mut ParseContext,
+    fields: &[&str],
+    index: usize,
+) -> Result<Recipient, FsdMessageParseError> {
+    let value = field(fields, index, "to")?;
+    match value.parse() {
+        Ok(recipient) => Ok(recipient),
+        Err(e) => {
+            let e: FsdMessageParseError = e.at_field(fields, index, "to");
+            let Some(callsign) = Callsign::lenient(value) else {
+                return Err(e);
+            };
+            cx.deviation(
+                ParseWarning::InvalidRecipient {
+                    value: value.to_string(),
+                },
+                e,
+            )?;
+            Ok(Recipient::Callsign(callsign))
+        }
+    }
+}
+
+/// Parses `value`, taken from field `index`, as a [`Callsign`] or [`Cid`][crate::Cid].
+///
+/// An identifier that breaks the naming rules is a deviation. In lenient mode, it is kept as long as it can
+/// still be sent back.
+pub(crate) fn identifier<T: Identifier>(
+    cx: &mut ParseContext,
+    value: &str,
+    fields: &[&str],
+    index: usize,
+    name: &'static str,
+) -> Result<T, FsdMessageParseError> {
+    match value.parse() {
+        Ok(identifier) => Ok(identifier),
+        Err(e) => {
+            let e = e.at_field(fields, index, name);
+            let Some(identifier) = T::lenient(value) else {
+                return Err(e);
+            };
+            cx.deviation(
+                ParseWarning::InvalidIdentifier {
+                    field: name,
+                    value: value.to_string(),
+                },
+                e,
+            )?;
+            Ok(identifier)
+        }
+    }
+}
+
+/// Parses field `index` as a [`Callsign`] or [`Cid`][crate::Cid]. See [`identifier`].
+#[inline]
+pub(crate) fn parse_identifier<T: Identifier>(
+    cx: &mut ParseContext,
+    fields: &[&str],
+    index: usize,
+    name: &'static str,
+) -> Result<T, FsdMessageParseError> {
+    identifier(cx, field(fields, index, name)?, fields, index, name)
+}
+
+/// Parses the first field, with its `prefix_len`-byte message prefix removed, as the sender's [`Callsign`]
+#[inline]
+pub(crate) fn parse_sender(
+    cx: &mut ParseContext,
+    fields: &[&str],
+    prefix_len: usize,
+    name: &'static str,
+) -> Result<Callsign, FsdMessageParseError> {
+    identifier(cx, strip_prefix(fields, prefix_len)?, fields, 0, name)
+}
+
+/// Parses field `index` using its [`FromStr`] implementation, attaching context to any error
+#[inline]
+pub(crate) fn parse_field<T: FromStr<Err = FsdMessageParseError>>(
+    fields: &[&str],
+    index: usize,
+    name: &'static str,
+) -> Result<T, FsdMessageParseError> {
+    field(fields, index, name)?
+        .parse()
+        .map_err(|e: FsdMessageParseError| e.at_field(fields, index, name))
+}
+
+/// Parses field `index`, mapping any failure to the error produced by `error`
+#[inline]
+pub(crate) fn parse_field_or<T: FromStr>(
+    fields: &[&str],
+    index: usize,
+    name: &'static str,
+    error: fn(String) -> FsdMessageParseError,
+) -> Result<T, FsdMessageParseError> {
+    let value = field(fields, index, name)?;
+    value
+        .parse()
+        .map_err(|_| error(value.to_string()).at_field(fields, index, name))
+}
+
+/// Parses field `index` as a [`Level`]. An empty level is a deviation, read as flight level zero in lenient mode.
+pub(crate) fn parse_level(
+    cx: &mut ParseContext,
+    fields: &[&str],
+    index: usize,
+    name: &'static str,
+) -> Result<Level, FsdMessageParseError> {
+    if field(fields, index, name)?.is_empty() {
+        cx.deviation(
+            ParseWarning::DefaultedField {
+                field: name,
+                value: String::new(),
+            },
+            FsdMessageParseError::InvalidLevel(String::new()).at_field(fields, index, name),
+        )?;
+    }
+    parse_field(fields, index, name)
+}
+
 pub fn encode_pitch_bank_heading(pitch: f64, bank: f64, heading: f64, on_ground: bool) -> u32 {
     let mut p = pitch / -360.0;
     if p < 0.0 {
@@ -60,61 +271,62 @@ pub fn split_frequencies(input: &str) -> Vec<RadioFrequency> {
         .collect()
 }
 
-pub(crate) fn group_frequencies_without_symbol(frequencies: &[RadioFrequency]) -> String {
-    let mut freqs_string = String::with_capacity(6 * frequencies.len() - 1);
+pub(crate) fn write_frequencies_without_symbol(
+    f: &mut std::fmt::Formatter<'_>,
+    frequencies: &[RadioFrequency],
+) -> std::fmt::Result {
     let mut freqs = frequencies.iter().peekable();
     while let Some(freq) = freqs.next() {
-        freqs_string.push_str(&freq.to_string());
+        write!(f, "{freq}")?;
         if freqs.peek().is_some() {
-            freqs_string.push('&');
+            f.write_str("&")?;
         }
     }
-    freqs_string
+    Ok(())
 }
 
-pub(crate) fn group_frequencies_with_symbol(frequencies: &[RadioFrequency]) -> String {
-    let mut freqs_string = String::with_capacity(6 * frequencies.len() - 1);
+pub(crate) fn write_frequencies_with_symbol(
+    f: &mut std::fmt::Formatter<'_>,
+    frequencies: &[RadioFrequency],
+) -> std::fmt::Result {
     let mut freqs = frequencies.iter().peekable();
     while let Some(freq) = freqs.next() {
-        freqs_string.push('@');
-        freqs_string.push_str(&freq.to_string());
+        write!(f, "@{freq}")?;
         if freqs.peek().is_some() {
-            freqs_string.push('&');
+            f.write_str("&")?;
         }
     }
-    freqs_string
+    Ok(())
 }
 
 // $CQEGCC_ATIS:@94835:NEWATIS:ATIS B:  31016KT Q1022
 pub(crate) fn parse_new_atis(
     input: &[&str],
 ) -> Result<(char, String, String), FsdMessageParseError> {
-    let first = input[0].to_uppercase();
-    let last = input[1].trim().to_uppercase();
+    let first = field(input, 0, "atis_letter")?.to_uppercase();
+    let last = field(input, 1, "atis")?.trim().to_uppercase();
+    let invalid = |index, name| {
+        FsdMessageParseError::InvalidNewAtisMessage(format!("{first}:{last}"))
+            .at_field(input, index, name)
+    };
     let atis_letter = first
         .chars()
         .last()
-        .ok_or_else(|| FsdMessageParseError::InvalidNewAtisMessage(format!("{first}:{last}")))?;
+        .ok_or_else(|| invalid(0, "atis_letter"))?;
     if (atis_letter as u8) < 65 || (atis_letter as u8) > 90 {
-        return Err(FsdMessageParseError::InvalidNewAtisMessage(format!(
-            "{first}:{last}"
-        )));
+        return Err(invalid(0, "atis_letter"));
     }
     let split = last.split(&[' ', '-']).collect::<Vec<&str>>();
 
     if split[0].len() < 7 {
-        return Err(FsdMessageParseError::InvalidNewAtisMessage(format!(
-            "{first}:{last}"
-        )));
+        return Err(invalid(1, "wind"));
     };
     let wind = split[0].to_string();
 
-    if split[1].len() < 4 {
-        return Err(FsdMessageParseError::InvalidNewAtisMessage(format!(
-            "{first}:{last}"
-        )));
+    let pressure = match split.get(1) {
+        Some(pressure) if pressure.len() >= 4 => pressure.to_string(),
+        _ => return Err(invalid(1, "pressure")),
     };
-    let pressure = split[1].to_string();
 
     Ok((atis_letter, wind, pressure))
 }
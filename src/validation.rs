@@ -0,0 +1,398 @@
+//! Checks that every field of a message can be serialised without breaking the line
+//!
+//! Callsigns, CIDs and recipients are validated when they are created, but free text fields are plain `String`s.
+//! A line break in any of them would end the line early, and a colon would shift every field after it, so
+//! [`FsdMessage::try_encode`][crate::FsdMessage::try_encode] rejects messages containing either.
+
+use crate::{
+    aircraft_config::AircraftConfig,
+    enums::{
+        AtisLine, ClientCapability, ClientQueryType, ClientResponseType, ScratchPad,
+        SharedStateType,
+    },
+    errors::{FieldViolation, FsdError},
+    messages::*,
+    structs::{FlightPlan, PlaneInfo},
+};
+
+/// Collects the fields of a message that can't be sent as they are
+#[derive(Debug, Default)]
+pub(crate) struct FieldChecker {
+    violations: Vec<FieldViolation>,
+}
+
+impl FieldChecker {
+    fn check(&mut self, field: &'static str, value: &str, forbidden: &[char]) {
+        for &character in forbidden {
+            if value.contains(character) {
+                self.violations.push(FieldViolation { field, character });
+            }
+        }
+    }
+
+    /// A field followed by other fields, which can't contain colons or line breaks
+    pub(crate) fn field(&mut self, field: &'static str, value: &str) {
+        self.check(field, value, &[':', '\r', '\n']);
+    }
+
+    /// The last field of a message that is only split as far as this field, which can contain colons
+    pub(crate) fn trailing(&mut self, field: &'static str, value: &str) {
+        self.check(field, value, &['\r', '\n']);
+    }
+
+    pub(crate) fn finish(self) -> Vec<FieldViolation> {
+        self.violations
+    }
+}
+
+/// A message, or part of one, with free text fields to check
+pub(crate) trait CheckFields {
+    fn check_fields(&self, check: &mut FieldChecker);
+}
+
+/// Returns every field of `message` that can't be sent as it is
+pub(crate) fn violations(message: &impl CheckFields) -> Vec<FieldViolation> {
+    let mut check = FieldChecker::default();
+    message.check_fields(&mut check);
+    check.finish()
+}
+
+/// Implements [`CheckFields`] for types whose fields are all validated on creation
+macro_rules! no_free_text {
+    ($($checked: ty),* $(,)?) => {
+        $(
+            impl CheckFields for $checked {
+                fn check_fields(&self, _check: &mut FieldChecker) {}
+            }
+        )*
+    };
+}
+
+no_free_text!(
+    AtcDeregisterMessage,
+    PilotDeregisterMessage,
+    AtcPositionUpdateMessage,
+    AtcSecondaryVisCentreMessage,
+    PilotPositionUpdateMessage,
+    SendFastPositionUpdatesMessage,
+    VelocityPositionStoppedMessage,
+    VelocityPositionSlowMessage,
+    VelocityPositionFastMessage,
+    TemperatureDataMessage,
+    WindDataMessage,
+    CloudDataMessage,
+    WindDeltaMessage,
+    PlaneInfoRequestMessage,
+    HandoffOfferMessage,
+    HandoffAcceptMessage,
+    AircraftConfig,
+);
+
+impl CheckFields for AtcRegisterMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("real_name", &self.real_name);
+        check.field("password", &self.password);
+    }
+}
+
+impl CheckFields for PilotRegisterMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("password", &self.password);
+        check.field("real_name", &self.real_name);
+    }
+}
+
+impl CheckFields for IvaoAtcRegisterMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.registration.check_fields(check);
+        check.field("software", &self.software);
+        check.field("version", &self.version);
+    }
+}
+
+impl CheckFields for IvaoPilotRegisterMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.registration.check_fields(check);
+        check.field("software", &self.software);
+        check.field("version", &self.version);
+    }
+}
+
+impl CheckFields for AuthenticationChallengeMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("challenge", &self.challenge);
+    }
+}
+
+impl CheckFields for AuthenticationResponseMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("response", &self.response);
+    }
+}
+
+impl CheckFields for TextMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.trailing("message", &self.message);
+    }
+}
+
+impl CheckFields for FrequencyMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.trailing("message", &self.message);
+    }
+}
+
+impl CheckFields for ChangeServerMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("hostname", &self.hostname);
+    }
+}
+
+impl CheckFields for InitialServerHandshakeMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("version", &self.version);
+        check.field("initial_key", &self.initial_key);
+    }
+}
+
+impl CheckFields for InitialClientHandshakeMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("client_name", &self.client_name);
+        check.field("guid", &self.guid);
+        if let Some(initial_key) = &self.initial_key {
+            check.field("initial_key", initial_key);
+        }
+    }
+}
+
+impl CheckFields for KillMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        if let Some(reason) = &self.reason {
+            check.field("reason", reason);
+        }
+    }
+}
+
+impl CheckFields for MetarRequestMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("station", &self.station);
+    }
+}
+
+impl CheckFields for MetarResponseMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("metar", &self.metar);
+    }
+}
+
+impl CheckFields for IvaoClientQueryMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("query_type", &self.query_type);
+        for data in &self.data {
+            check.field("data", data);
+        }
+    }
+}
+
+impl CheckFields for WeatherRequestMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("station", &self.station);
+    }
+}
+
+impl CheckFields for PingMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("data", &self.data);
+    }
+}
+
+impl CheckFields for PongMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("data", &self.data);
+    }
+}
+
+impl CheckFields for PlaneInfo {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        if let Some(equipment) = &self.equipment {
+            check.field("equipment", equipment);
+        }
+        if let Some(airline) = &self.airline {
+            check.field("airline", airline);
+        }
+        if let Some(livery) = &self.livery {
+            check.field("livery", livery);
+        }
+    }
+}
+
+impl CheckFields for PlaneInfoResponseMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.plane_info.check_fields(check);
+    }
+}
+
+impl CheckFields for FSInnPlaneInformationRequestMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("airline_icao", &self.airline_icao);
+        check.field("aircraft_icao", &self.aircraft_icao);
+        check.field("combined_type", &self.combined_type);
+        check.field("model_string", &self.model_string);
+    }
+}
+
+impl CheckFields for FSInnPlaneInformationResponseMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("airline_icao", &self.airline_icao);
+        check.field("aircraft_icao", &self.aircraft_icao);
+        check.field("combined_type", &self.combined_type);
+        check.field("model_string", &self.model_string);
+    }
+}
+
+impl CheckFields for FsdErrorMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        // Only the message of an `Other` error is sent
+        if let FsdError::Other(message) = &self.error_type {
+            check.field("message", message);
+        }
+    }
+}
+
+impl CheckFields for FlightPlan {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        check.field("ac_type", &self.ac_type);
+        check.field("origin", &self.origin);
+        check.field("destination", &self.destination);
+        check.field("alternate", &self.alternate);
+        check.field("remarks", &self.remarks);
+        check.field("route", &self.route);
+    }
+}
+
+impl CheckFields for FlightPlanMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.flight_plan.check_fields(check);
+    }
+}
+
+impl CheckFields for FlightPlanAmendmentMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.flight_plan.check_fields(check);
+    }
+}
+
+impl CheckFields for ScratchPad {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        match self {
+            ScratchPad::PlainTextOrDirect(text) => check.field("contents", text),
+            ScratchPad::Stand(stand) => check.field("stand", stand),
+            ScratchPad::ManualStand(icao, stand) => {
+                check.field("icao", icao);
+                check.field("stand", stand);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl CheckFields for ClientQueryType {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        match self {
+            ClientQueryType::HelpRequest {
+                message: Some(message),
+            }
+            | ClientQueryType::CancelHelpRequest {
+                message: Some(message),
+            } => check.field("message", message),
+            ClientQueryType::SetScratchpad { contents, .. } => contents.check_fields(check),
+            ClientQueryType::AircraftConfigurationResponse { aircraft_config } => {
+                aircraft_config.check_fields(check)
+            }
+            ClientQueryType::NewATIS {
+                surface_wind,
+                pressure,
+                ..
+            } => {
+                check.field("surface_wind", surface_wind);
+                check.field("pressure", pressure);
+            }
+            ClientQueryType::SetGlobalData { contents, .. } => check.field("contents", contents),
+            ClientQueryType::Estimate { fix, .. } => check.field("fix", fix),
+            _ => {}
+        }
+    }
+}
+
+impl CheckFields for ClientQueryMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.query_type.check_fields(check);
+    }
+}
+
+impl CheckFields for AtisLine {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        match self {
+            AtisLine::VoiceServer(voice_server) => check.field("voice_server", voice_server),
+            // Text lines are reassembled from every remaining field, so they can contain colons
+            AtisLine::TextLine(text) => check.trailing("text", text),
+            _ => {}
+        }
+    }
+}
+
+impl CheckFields for ClientResponseType {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        match self {
+            ClientResponseType::ATIS { atis_line } => atis_line.check_fields(check),
+            ClientResponseType::Capabilities { capabilities } => {
+                for capability in capabilities {
+                    if let ClientCapability::Unknown(name) = capability {
+                        check.field("capabilities", name);
+                    }
+                }
+            }
+            ClientResponseType::RealName {
+                name, sector_file, ..
+            } => {
+                check.field("name", name);
+                check.field("sector_file", sector_file);
+            }
+            ClientResponseType::PublicIP { ip_address } => check.field("ip_address", ip_address),
+            ClientResponseType::Server {
+                hostname_or_ip_address,
+            } => check.field("hostname_or_ip_address", hostname_or_ip_address),
+            _ => {}
+        }
+    }
+}
+
+impl CheckFields for ClientQueryResponseMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.response_type.check_fields(check);
+    }
+}
+
+impl CheckFields for SharedStateType {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        match self {
+            SharedStateType::ScratchPad { contents, .. } => contents.check_fields(check),
+            SharedStateType::FlightStrip {
+                contents: Some(contents),
+                ..
+            } => {
+                for item in contents {
+                    check.field("contents", item);
+                }
+            }
+            SharedStateType::GlobalData { contents, .. } => check.field("contents", contents),
+            _ => {}
+        }
+    }
+}
+
+impl CheckFields for SharedStateMessage {
+    fn check_fields(&self, check: &mut FieldChecker) {
+        self.shared_state_type.check_fields(check);
+    }
+}